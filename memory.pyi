@@ -3,11 +3,13 @@ from __future__ import annotations
 from typing import Any, Mapping, Sequence
 
 class PyMetadataIndex:
-    def __init__(self, path: str | None = None) -> None: ...
+    # threads sizes a dedicated search thread pool (0 = rayon default); the global pool is left untouched
+    def __init__(self, path: str | None = None, threads: int = 0) -> None: ...
     def batch_add(self, entries: Sequence[Mapping[str, Any]]) -> int: ...
     def search_flexible(self, params: Mapping[str, Any]) -> list[str]: ...
     def search_strict(self, params: Mapping[str, Any]) -> list[str]: ...
     def stats(self) -> dict[str, int]: ...
+    def get_thread_count(self) -> int: ...
     def save(self) -> bool: ...
 
 __all__ = ["PyMetadataIndex"]
@@ -9,7 +9,11 @@ pub mod memory;
 pub use memory::PyMetadataIndex;
 
 pub mod video; // 视频相关
-pub use video::{PyPerformanceResult, PyVideoFrame, VideoKeyframeExtractor};
+use std::path::PathBuf;
+use video::naming::SaveOptions;
+use video::source::SourceArg;
+use video::utils::SelectionOptions;
+pub use video::{PyBatchItem, PyBatchResult, PyCropRect, PyDifferenceCurve, PyEncodedKeyframe, PyFrameMask, PyFrameSource, PyFrameStats, PyFrozenSegment, PyLoopInfo, PyPerformanceResult, PySpriteSheet, PyStoryboard, PyTransitionSegment, PyVideoFrame, VideoKeyframeExtractor};
 
 // -------------------------------------------------------------------------------------------------
 // 辅助：创建并注册子模块
@@ -19,50 +23,66 @@ pub use video::{PyPerformanceResult, PyVideoFrame, VideoKeyframeExtractor};
 // -------------------------------------------------------------------------------------------------
 // 辅助函数: 便捷调用
 // -------------------------------------------------------------------------------------------------
-#[pyfunction]
-#[pyo3(signature = (video_path, output_dir, max_keyframes, max_save=None, ffmpeg_path=None, use_simd=None, threads=None, verbose=None, block_size=None))]
-fn extract_keyframes_from_video(
-    video_path: &str,
-    output_dir: &str,
-    max_keyframes: usize,
-    max_save: Option<usize>,
-    ffmpeg_path: Option<String>,
-    use_simd: Option<bool>,
-    threads: Option<usize>,
-    verbose: Option<bool>,
-    block_size: Option<usize>
-) -> PyResult<PyPerformanceResult> {
-    let extractor = VideoKeyframeExtractor::new(
-        ffmpeg_path.unwrap_or_else(|| "ffmpeg".to_string()),
-        threads.unwrap_or(0),
-        verbose.unwrap_or(false)
-    )?;
-    extractor.process_video(
-        video_path,
-        output_dir,
-        max_keyframes,
-        max_save,
-        use_simd,
-        block_size
-    )
-}
+// pyo3 0.22 为 #[pyfunction] 生成的同级包装函数会对 PyResult 的 PyErr 再 .into() 一次；
+// 写在函数上的 allow 带不到包装函数上，只能放在包含两者的模块上
+#[allow(clippy::useless_conversion)]
+mod functions {
+    use super::*;
 
-#[pyfunction]
-fn get_system_info(py: Python<'_>) -> PyResult<Py<PyDict>> {
-    let dict = PyDict::new_bound(py);
-    dict.set_item("threads", rayon::current_num_threads())?;
-    #[cfg(target_arch = "x86_64")]
-    {
-        dict.set_item("avx2_supported", std::arch::is_x86_feature_detected!("avx2"))?;
-        dict.set_item("sse2_supported", std::arch::is_x86_feature_detected!("sse2"))?;
+    #[pyfunction]
+    #[pyo3(signature = (video_path, output_dir, max_keyframes, max_save=None, ffmpeg_path=None, use_simd=None, threads=None, verbose=None, block_size=None))]
+    pub(super) fn extract_keyframes_from_video(
+        py: Python<'_>,
+        video_path: SourceArg,
+        output_dir: PathBuf,
+        max_keyframes: usize,
+        max_save: Option<usize>,
+        ffmpeg_path: Option<String>,
+        use_simd: Option<bool>,
+        threads: Option<usize>,
+        verbose: Option<bool>,
+        block_size: Option<usize>
+    ) -> PyResult<PyPerformanceResult> {
+        let extractor = VideoKeyframeExtractor::new(
+            ffmpeg_path.unwrap_or_else(|| "ffmpeg".to_string()),
+            threads.unwrap_or(0),
+            verbose.unwrap_or(false),
+            1,
+            None
+        )?;
+        extractor.process_video_with_options(
+            &video_path.input(py)?,
+            &output_dir,
+            max_keyframes,
+            max_save,
+            use_simd,
+            block_size,
+            SelectionOptions::default(),
+            &SaveOptions::default(),
+            false
+        )
     }
-    #[cfg(not(target_arch = "x86_64"))]
-    {
-        dict.set_item("simd_supported", false)?;
+
+    #[pyfunction]
+    pub(super) fn get_system_info(py: Python<'_>) -> PyResult<Py<PyDict>> {
+        let dict = PyDict::new_bound(py);
+        // 全局 rayon 池的线程数，"threads" 为旧键名保留；各提取器/索引使用自己的线程池，见其 get_thread_count()
+        dict.set_item("threads", rayon::current_num_threads())?;
+        dict.set_item("global_threads", rayon::current_num_threads())?;
+        #[cfg(target_arch = "x86_64")]
+        {
+            dict.set_item("avx2_supported", std::arch::is_x86_feature_detected!("avx2"))?;
+            dict.set_item("sse2_supported", std::arch::is_x86_feature_detected!("sse2"))?;
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            dict.set_item("simd_supported", false)?;
+        }
+        dict.set_item("version", env!("CARGO_PKG_VERSION"))?;
+        Ok(dict.into())
     }
-    dict.set_item("version", env!("CARGO_PKG_VERSION"))?;
-    Ok(dict.into())
 }
+use functions::{extract_keyframes_from_video, get_system_info};
 
 // -------------------------------------------------------------------------------------------------
 // 顶层模块
@@ -73,6 +93,19 @@ fn inkfox(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyVideoFrame>()?;
     m.add_class::<PyPerformanceResult>()?;
     m.add_class::<VideoKeyframeExtractor>()?;
+    m.add_class::<PyTransitionSegment>()?;
+    m.add_class::<PyDifferenceCurve>()?;
+    m.add_class::<PyFrameMask>()?;
+    m.add_class::<PyCropRect>()?;
+    m.add_class::<PyFrameStats>()?;
+    m.add_class::<PyFrozenSegment>()?;
+    m.add_class::<PyLoopInfo>()?;
+    m.add_class::<PyFrameSource>()?;
+    m.add_class::<PyEncodedKeyframe>()?;
+    m.add_class::<PyStoryboard>()?;
+    m.add_class::<PySpriteSheet>()?;
+    m.add_class::<PyBatchItem>()?;
+    m.add_class::<PyBatchResult>()?;
     m.add_class::<PyMetadataIndex>()?;
     m.add_function(wrap_pyfunction!(extract_keyframes_from_video, m.clone())?)?;
     m.add_function(wrap_pyfunction!(get_system_info, m.clone())?)?;
@@ -82,12 +115,38 @@ fn inkfox(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     video_mod.add_class::<PyVideoFrame>()?;
     video_mod.add_class::<PyPerformanceResult>()?;
     video_mod.add_class::<VideoKeyframeExtractor>()?;
+    video_mod.add_class::<PyTransitionSegment>()?;
+    video_mod.add_class::<PyDifferenceCurve>()?;
+    video_mod.add_class::<PyFrameMask>()?;
+    video_mod.add_class::<PyCropRect>()?;
+    video_mod.add_class::<PyFrameStats>()?;
+    video_mod.add_class::<PyFrozenSegment>()?;
+    video_mod.add_class::<PyLoopInfo>()?;
+    video_mod.add_class::<PyFrameSource>()?;
+    video_mod.add_class::<PyEncodedKeyframe>()?;
+    video_mod.add_class::<PyStoryboard>()?;
+    video_mod.add_class::<PySpriteSheet>()?;
+    video_mod.add_class::<PyBatchItem>()?;
+    video_mod.add_class::<PyBatchResult>()?;
     video_mod.setattr("extract_keyframes_from_video", m.getattr("extract_keyframes_from_video")?)?;
     video_mod.setattr("get_system_info", m.getattr("get_system_info")?)?;
     let video_all = PyList::new_bound(py, [
         "PyVideoFrame",
         "PyPerformanceResult",
         "VideoKeyframeExtractor",
+        "PyTransitionSegment",
+        "PyDifferenceCurve",
+        "PyFrameMask",
+        "PyCropRect",
+        "PyFrameStats",
+        "PyFrozenSegment",
+        "PyLoopInfo",
+        "PyFrameSource",
+        "PyEncodedKeyframe",
+        "PyStoryboard",
+        "PySpriteSheet",
+        "PyBatchItem",
+        "PyBatchResult",
         "extract_keyframes_from_video",
         "get_system_info",
     ]);
@@ -124,6 +183,19 @@ fn inkfox(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     py.get_type_bound::<PyVideoFrame>().setattr("__module__", "inkfox.video").ok();
     py.get_type_bound::<PyPerformanceResult>().setattr("__module__", "inkfox.video").ok();
     py.get_type_bound::<VideoKeyframeExtractor>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyTransitionSegment>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyDifferenceCurve>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyFrameMask>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyCropRect>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyFrameStats>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyFrozenSegment>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyLoopInfo>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyFrameSource>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyEncodedKeyframe>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyStoryboard>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PySpriteSheet>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyBatchItem>().setattr("__module__", "inkfox.video").ok();
+    py.get_type_bound::<PyBatchResult>().setattr("__module__", "inkfox.video").ok();
     py.get_type_bound::<PyMetadataIndex>().setattr("__module__", "inkfox.memory").ok();
 
     // 顶层 __all__
@@ -132,6 +204,19 @@ fn inkfox(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
         "PyVideoFrame",
         "PyPerformanceResult",
         "VideoKeyframeExtractor",
+        "PyTransitionSegment",
+        "PyDifferenceCurve",
+        "PyFrameMask",
+        "PyCropRect",
+        "PyFrameStats",
+        "PyFrozenSegment",
+        "PyLoopInfo",
+        "PyFrameSource",
+        "PyEncodedKeyframe",
+        "PyStoryboard",
+        "PySpriteSheet",
+        "PyBatchItem",
+        "PyBatchResult",
         "extract_keyframes_from_video",
         "get_system_info",
         "video",
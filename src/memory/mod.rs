@@ -1,6 +1,10 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
 use pyo3::types::{PyAny, PyDict, PyList};
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -117,15 +121,20 @@ impl InnerIndex {
 pub struct PyMetadataIndex {
     inner: Arc<InnerIndex>,
     path: Option<PathBuf>,
+    /// 搜索使用的独立线程池，不影响全局池
+    pool: Arc<ThreadPool>,
 }
 
 #[pymethods]
 impl PyMetadataIndex {
     #[new]
-    #[pyo3(signature = (path=None))]
-    pub fn new(path: Option<String>) -> PyResult<Self> {
+    /// threads=0 时按 rayon 默认（RAYON_NUM_THREADS 或 CPU 核数）
+    #[pyo3(signature = (path=None, threads=0))]
+    pub fn new(path: Option<String>, threads: usize) -> PyResult<Self> {
         let idx = InnerIndex::default();
-        let inst = PyMetadataIndex { inner: Arc::new(idx), path: path.map(PathBuf::from) };
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to create thread pool: {}", e)))?;
+        let inst = PyMetadataIndex { inner: Arc::new(idx), path: path.map(PathBuf::from), pool: Arc::new(pool) };
         if let Some(p) = &inst.path {
             if p.exists() {
                 if let Ok(bytes) = fs::read(p) {
@@ -161,28 +170,32 @@ impl PyMetadataIndex {
         let entries: Vec<MetadataEntry> = self.inner.entries.iter().map(|e| e.value().clone()).collect();
         let subjects_lc: Vec<String> = subjects.clone().unwrap_or_default().into_iter().map(|s| s.to_lowercase()).collect();
 
-        let mut scored: Vec<(f32, String, f64)> = entries.par_iter().filter_map(|e| {
-            if let Some(uid) = &user_id { if &e.user_id != uid { return None; } }
-            // 时间过滤
-            if let Some(ca) = created_after { if e.created_at < ca { return None; } }
-            if let Some(cb) = created_before { if e.created_at > cb { return None; } }
-
-            let mut score = 0f32;
-            // 1. 类型
-            if let Some(ts) = &types { if !ts.is_empty() { let mut tscore = 0f32; for t in ts { if e.memory_type == *t { tscore = 1.0; break; } else if e.memory_type.contains(t) || t.contains(&e.memory_type) { tscore = 0.5; break; } } score += tscore; } }
-            // 2. 主语
-            if !subjects_lc.is_empty() { let mut sscore = 0f32; for s in &subjects_lc { for es in &e.subjects { let esn = es.to_lowercase(); if esn == *s { sscore = 1.0; break; } if esn.contains(s) || s.contains(&esn) { sscore = 0.6; break; } } if sscore > 0.0 { break; } } score += sscore; }
-            // 3. 宾语与主语关联
-            if !subjects_lc.is_empty() && !e.objects.is_empty() { let mut os = 0f32; 'outer: for o in &e.objects { let on = o.to_lowercase(); for s in &subjects_lc { if on.contains(s) || s.contains(&on) { os = 0.8; break 'outer; } } } score += os; }
-            // 4. 时间加分（如果传了范围且通过）
-            if created_after.is_some() || created_before.is_some() { score += 1.0; }
-            if score >= 2.0 { Some((score, e.memory_id.clone(), e.created_at)) } else { None }
-        }).collect();
-
-        // 排序: 分数优先，时间次之（新 -> 旧）
-        scored.par_sort_unstable_by(|a,b| {
-            b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
-                .then_with(|| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal))
+        // 打分与排序在本索引的线程池中并行执行
+        let scored = self.pool.install(|| {
+            let mut scored: Vec<(f32, String, f64)> = entries.par_iter().filter_map(|e| {
+                if let Some(uid) = &user_id { if &e.user_id != uid { return None; } }
+                // 时间过滤
+                if let Some(ca) = created_after { if e.created_at < ca { return None; } }
+                if let Some(cb) = created_before { if e.created_at > cb { return None; } }
+
+                let mut score = 0f32;
+                // 1. 类型
+                if let Some(ts) = &types { if !ts.is_empty() { let mut tscore = 0f32; for t in ts { if e.memory_type == *t { tscore = 1.0; break; } else if e.memory_type.contains(t) || t.contains(&e.memory_type) { tscore = 0.5; break; } } score += tscore; } }
+                // 2. 主语
+                if !subjects_lc.is_empty() { let mut sscore = 0f32; for s in &subjects_lc { for es in &e.subjects { let esn = es.to_lowercase(); if esn == *s { sscore = 1.0; break; } if esn.contains(s) || s.contains(&esn) { sscore = 0.6; break; } } if sscore > 0.0 { break; } } score += sscore; }
+                // 3. 宾语与主语关联
+                if !subjects_lc.is_empty() && !e.objects.is_empty() { let mut os = 0f32; 'outer: for o in &e.objects { let on = o.to_lowercase(); for s in &subjects_lc { if on.contains(s) || s.contains(&on) { os = 0.8; break 'outer; } } } score += os; }
+                // 4. 时间加分（如果传了范围且通过）
+                if created_after.is_some() || created_before.is_some() { score += 1.0; }
+                if score >= 2.0 { Some((score, e.memory_id.clone(), e.created_at)) } else { None }
+            }).collect();
+
+            // 排序: 分数优先，时间次之（新 -> 旧）
+            scored.par_sort_unstable_by(|a,b| {
+                b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal))
+            });
+            scored
         });
         Ok(scored.into_iter().take(limit).map(|(_, id, _)| id).collect())
     }
@@ -240,6 +253,8 @@ impl PyMetadataIndex {
         })
     }
 
+    pub fn get_thread_count(&self) -> usize { self.pool.current_num_threads() }
+
     pub fn save(&self) -> PyResult<bool> {
         if let Some(p) = &self.path { 
             let list: Vec<MetadataEntry> = self.inner.entries.iter().map(|e| e.value().clone()).collect();
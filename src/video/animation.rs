@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, Frame, Frames, RgbImage, RgbaImage};
+
+use crate::video::frame::{PyVideoFrame, DEFAULT_FPS};
+use crate::video::input::VideoInput;
+use crate::video::store::FrameData;
+use crate::video::utils::VideoInfo;
+
+/// 可在 Rust 内直接解码的动图格式（静态 PNG/WebP 按单帧处理）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat { Gif, Png, WebP }
+
+/// 与浏览器一致：延迟 <= 10ms 的 GIF 帧按 100ms 播放
+const MIN_DELAY_SECS: f64 = 0.011;
+const FALLBACK_DELAY_SECS: f64 = 0.1;
+
+impl AnimationFormat {
+    /// 按文件头（至少 12 字节）识别，不依赖扩展名
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        if head.len() < 12 { return None; }
+        if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") { Some(Self::Gif) }
+        else if head.starts_with(b"\x89PNG\r\n\x1a\n") { Some(Self::Png) }
+        else if &head[0..4] == b"RIFF" && &head[8..12] == b"WEBP" { Some(Self::WebP) }
+        else { None }
+    }
+}
+
+/// 逐帧解码为整幅画布（已处理 disposal/blend），附带每帧显示时长
+fn decode_frames(input: &VideoInput, format: AnimationFormat) -> Result<Frames<'static>> {
+    let still = |img: DynamicImage| Frames::new(Box::new(std::iter::once(Ok(Frame::new(img.to_rgba8())))));
+    Ok(match format {
+        AnimationFormat::Gif => GifDecoder::new(input.reader()?)?.into_frames(),
+        AnimationFormat::Png => {
+            let decoder = PngDecoder::new(input.reader()?)?;
+            if decoder.is_apng()? { decoder.apng()?.into_frames() } else { still(DynamicImage::from_decoder(decoder)?) }
+        }
+        AnimationFormat::WebP => {
+            let decoder = WebPDecoder::new(input.reader()?)?;
+            if decoder.has_animation() { decoder.into_frames() } else { still(DynamicImage::from_decoder(decoder)?) }
+        }
+    })
+}
+
+fn delay_secs(frame: &Frame) -> f64 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    let secs = numer as f64 / denom.max(1) as f64 / 1000.0;
+    if secs < MIN_DELAY_SECS { FALLBACK_DELAY_SECS } else { secs }
+}
+
+/// 透明像素按黑底合成，灰度系数与 ffmpeg 的 gray 输出一致（BT.601）
+pub fn to_gray(img: &RgbaImage) -> Vec<u8> {
+    img.pixels().map(|p| {
+        let [r, g, b, a] = p.0;
+        let y = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+        (y * a as u32 / 255) as u8
+    }).collect()
+}
+
+fn to_rgb(img: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(img.width(), img.height(), |x, y| { let [r, g, b, a] = img.get_pixel(x, y).0; image::Rgb([r, g, b].map(|c| (c as u32 * a as u32 / 255) as u8)) })
+}
+
+/// 与 `stream_frames` 相同的回调约定；时间戳为累计显示时长，fps 为平均帧率
+pub fn stream_animation<F: FnMut(&PyVideoFrame) -> bool>(input: &VideoInput, format: AnimationFormat, mut on_frame: F) -> Result<(VideoInfo, usize)> {
+    let (mut width, mut height, mut count, mut clock) = (0, 0, 0, 0.0);
+    for frame in decode_frames(input, format)? {
+        let frame = frame.context("Failed to decode animation frame")?;
+        let delay = delay_secs(&frame); let img = frame.into_buffer();
+        (width, height) = (img.width() as usize, img.height() as usize);
+        let keep_going = on_frame(&PyVideoFrame::from_data(count, width, height, FrameData::Owned(to_gray(&img)), clock));
+        count += 1; clock += delay;
+        if !keep_going { break; }
+    }
+    if count == 0 { anyhow::bail!("No frames decoded from {}", input.display_name()); }
+    let fps = if count > 1 && clock > 0.0 { count as f64 / clock } else { DEFAULT_FPS };
+    Ok((VideoInfo { width, height, fps }, count))
+}
+
+/// 重新解码并取出指定帧的 RGB 画面（透明处按黑底合成），按帧号升序返回 (帧号, 时间戳, 画面)
+pub fn decode_rgb_frames(input: &VideoInput, format: AnimationFormat, indices: &[usize]) -> Result<Vec<(usize, f64, RgbImage)>> {
+    let mut wanted = indices.to_vec(); wanted.sort_unstable(); wanted.dedup();
+    let last = match wanted.last() { Some(&m) => m, None => return Ok(Vec::new()) };
+    let (mut out, mut clock) = (Vec::with_capacity(wanted.len()), 0.0);
+    for (idx, frame) in decode_frames(input, format)?.enumerate().take(last + 1) {
+        let frame = frame.context("Failed to decode animation frame")?;
+        if wanted.binary_search(&idx).is_ok() { out.push((idx, clock, to_rgb(frame.buffer()))); }
+        clock += delay_secs(&frame);
+    }
+    Ok(out)
+}
@@ -0,0 +1,104 @@
+use chrono::prelude::*;
+use pyo3::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::video::input::VideoInput;
+use crate::video::performance::PyPerformanceResult;
+
+/// 批处理中单个视频的结果；失败时 result 为 None，error 为错误信息
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyBatchItem {
+    #[pyo3(get)] pub video_file: String,
+    #[pyo3(get)] pub output_dir: PathBuf,
+    #[pyo3(get)] pub result: Option<PyPerformanceResult>,
+    #[pyo3(get)] pub error: Option<String>,
+}
+
+#[pymethods]
+impl PyBatchItem {
+    #[getter]
+    fn ok(&self) -> bool { self.error.is_none() }
+    fn __repr__(&self) -> String {
+        match (&self.result, &self.error) {
+            (Some(r), _) => format!("PyBatchItem('{}', ok, frames={}, keyframes={}, {:.0} ms)", self.video_file, r.total_frames, r.keyframes_extracted, r.total_time_ms),
+            (None, e) => format!("PyBatchItem('{}', error={:?})", self.video_file, e.as_deref().unwrap_or("")),
+        }
+    }
+}
+
+/// 批处理结果：按输入顺序的逐视频条目，以及汇总统计（耗时为整批墙钟时间，帧数/关键帧数为各视频之和）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyBatchResult {
+    #[pyo3(get)] pub items: Vec<PyBatchItem>,
+    #[pyo3(get)] pub summary: PyPerformanceResult,
+    #[pyo3(get)] pub succeeded: usize,
+    #[pyo3(get)] pub failed: usize,
+}
+
+#[pymethods]
+impl PyBatchResult {
+    /// 失败视频的 (文件名, 错误信息)，按输入顺序；同名文件可能出现多次
+    fn errors(&self) -> Vec<(String, String)> { self.items.iter().filter_map(|i| Some((i.video_file.clone(), i.error.clone()?))).collect() }
+    fn __len__(&self) -> usize { self.items.len() }
+    fn __repr__(&self) -> String { format!("PyBatchResult({} videos, {} succeeded, {} failed, {:.0} ms)", self.items.len(), self.succeeded, self.failed, self.summary.total_time_ms) }
+}
+
+/// 批处理的一个任务：显示名、输出目录、已解析的输入（解析失败时为错误信息）
+pub struct BatchJob { pub name: String, pub output_dir: PathBuf, pub input: Result<VideoInput, String> }
+
+/// 每个视频输出到 output_dir 下以文件名（不含扩展名）命名的子目录，重名时追加 _1、_2 ...
+pub fn batch_output_dirs(output_dir: &Path, stems: &[String]) -> Vec<PathBuf> {
+    let mut taken = HashSet::new();
+    stems.iter().map(|stem| {
+        let name = (0..).map(|n| if n == 0 { stem.clone() } else { format!("{}_{}", stem, n) }).find(|n| !taken.contains(n)).expect("unbounded search");
+        taken.insert(name.clone()); output_dir.join(name)
+    }).collect()
+}
+
+/// 以共享任务队列并发处理：至多 `concurrency` 个视频同时解码，各自的分析阶段共用 rayon 线程池。
+/// 单个视频失败只记入对应条目，不中断整批
+/// `threads` 为分析线程池大小，只用于汇总统计
+pub fn run_batch<F>(jobs: Vec<BatchJob>, concurrency: usize, max_keyframes: usize, threads: usize, verbose: bool, process: F) -> PyBatchResult
+where F: Fn(&VideoInput, &Path) -> Result<PyPerformanceResult, String> + Sync {
+    let start = Instant::now(); let total = jobs.len();
+    let next = AtomicUsize::new(0); let done = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<Result<PyPerformanceResult, String>>>> = jobs.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|s| {
+        for _ in 0..concurrency.clamp(1, total.max(1)) {
+            s.spawn(|| loop {
+                let k = next.fetch_add(1, Ordering::Relaxed); let Some(job) = jobs.get(k) else { break };
+                let outcome = job.input.clone().and_then(|input| process(&input, &job.output_dir));
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if verbose { match &outcome { Ok(_) => println!("[{}/{}] {} done", n, total, job.name), Err(e) => println!("[{}/{}] {} failed: {}", n, total, job.name, e) } }
+                *slots[k].lock().expect("batch slot poisoned") = Some(outcome);
+            });
+        }
+    });
+    let items: Vec<PyBatchItem> = jobs.into_iter().zip(slots).map(|(job, slot)| {
+        let outcome = slot.into_inner().expect("batch slot poisoned").unwrap_or_else(|| Err("Worker did not finish".into()));
+        let (result, error) = match outcome { Ok(r) => (Some(r), None), Err(e) => (None, Some(e)) };
+        PyBatchItem { video_file: job.name, output_dir: job.output_dir, result, error }
+    }).collect();
+    let summary = summarize(&items, start.elapsed().as_secs_f64() * 1000.0, max_keyframes, concurrency, threads);
+    let succeeded = items.iter().filter(|i| i.error.is_none()).count();
+    PyBatchResult { failed: items.len() - succeeded, succeeded, items, summary }
+}
+
+fn summarize(items: &[PyBatchItem], wall_ms: f64, max_keyframes: usize, concurrency: usize, threads: usize) -> PyPerformanceResult {
+    let ok: Vec<&PyPerformanceResult> = items.iter().filter_map(|i| i.result.as_ref()).collect();
+    let (frames, keyframes) = (ok.iter().map(|r| r.total_frames).sum::<usize>(), ok.iter().map(|r| r.keyframes_extracted).sum::<usize>());
+    PyPerformanceResult {
+        test_name: "Batch Processing".into(), video_file: format!("{}/{} videos", ok.len(), items.len()), total_time_ms: wall_ms,
+        frame_extraction_time_ms: ok.iter().map(|r| r.frame_extraction_time_ms).sum(), keyframe_analysis_time_ms: ok.iter().map(|r| r.keyframe_analysis_time_ms).sum(),
+        total_frames: frames, keyframes_extracted: keyframes, keyframe_ratio: if frames > 0 { keyframes as f64 / frames as f64 * 100.0 } else { 0.0 },
+        processing_fps: frames as f64 / (wall_ms / 1000.0), max_keyframes_requested: max_keyframes,
+        optimization_type: match ok.first() { Some(r) => format!("Batch(concurrency:{}) {}", concurrency, r.optimization_type), None => format!("Batch(concurrency:{})", concurrency) },
+        simd_enabled: ok.first().is_some_and(|r| r.simd_enabled), threads_used: threads, timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::input::VideoInput;
+use crate::video::store::FrameStore;
+use crate::video::utils::{parse_probe_info, parse_video_duration, probe_text, VideoInfo};
+
+/// 每段至少这么长才值得单独起一个 ffmpeg
+const MIN_CHUNK_SECS: f64 = 2.0;
+/// 各段从边界前几帧处开始定位，避免 ffmpeg 精确定位的取整吃掉边界帧
+const SEEK_MARGIN_FRAMES: f64 = 2.0;
+
+/// n 段之间的 n-1 个边界（秒）。边界取在两帧正中，保证每帧按时间戳只落入一段
+fn chunk_bounds(duration: f64, fps: f64, n: usize) -> Vec<f64> {
+    (1..n).map(|k| ((k as f64 * duration / n as f64 * fps).round() - 0.5) / fps).collect()
+}
+
+/// 解析 showinfo 日志：先记录 "config in time_base: a/b"，之后每帧一行 "n: .. pts: .."。
+/// 旧版 ffmpeg 没有 time_base 行时退回 pts_time（精度较低）
+fn parse_showinfo(line: &str, time_base: &mut Option<f64>) -> Option<f64> {
+    if let Some(tb) = line.split("config in time_base:").nth(1) {
+        let (num, den) = tb.trim().split(',').next()?.split_once('/')?;
+        *time_base = Some(num.trim().parse::<f64>().ok()? / den.trim().parse::<f64>().ok()?);
+        return None;
+    }
+    if !line.contains("Parsed_showinfo") { return None; }
+    let mut tokens = line.split_whitespace();
+    match *time_base {
+        Some(tb) => { tokens.find(|t| *t == "pts:")?; Some(tokens.next()?.parse::<i64>().ok()? as f64 * tb) }
+        None => tokens.find_map(|t| t.strip_prefix("pts_time:")?.parse().ok()),
+    }
+}
+
+/// 串行与分段解码共用的输出参数：showinfo 给出每帧 pts，passthrough 不补帧也不丢帧，两条路径输出同样的帧序列
+pub const GRAY_OUTPUT_ARGS: [&str; 10] = ["-an", "-vf", "showinfo", "-vsync", "passthrough", "-f", "rawvideo", "-pix_fmt", "gray", "-"];
+
+/// 逐帧把 ffmpeg（以 `GRAY_OUTPUT_ARGS` 输出，stdout/stderr 均为管道）的灰度帧读入复用的 `frame`，帧号为读出序号，时间戳取 showinfo 的 pts。
+/// `seek` 为 -ss 定位点，时间戳加回后取整到微秒，串行与分段解码得到逐位相同的值。
+/// 回调返回 false 时停止；结束时终止并回收子进程。返回读出的帧数
+pub fn read_gray_frames(child: &mut Child, frame: &mut PyVideoFrame, seek: f64, mut on_frame: impl FnMut(&PyVideoFrame) -> Result<bool>) -> Result<usize> {
+    let (stderr, stdout) = (child.stderr.take().context("FFmpeg stderr unavailable")?, child.stdout.take().context("FFmpeg stdout unavailable")?);
+    // showinfo 在帧写出前打印，单独线程持续读取 stderr 以免管道写满阻塞 ffmpeg
+    let (pts_tx, pts_rx) = mpsc::channel();
+    let log = std::thread::spawn(move || {
+        let mut time_base = None;
+        for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) { if let Some(t) = parse_showinfo(&line, &mut time_base) { if pts_tx.send(t).is_err() { break; } } }
+    });
+    let mut reader = BufReader::with_capacity(1024*1024, stdout);
+    let mut count = 0;
+    let result = loop {
+        if reader.read_exact(frame.pixels_mut()).is_err() { break Ok(count); }
+        let Ok(t) = pts_rx.recv() else { break Err(anyhow::anyhow!("FFmpeg showinfo output missing frame timestamps")) };
+        (frame.frame_number, frame.timestamp) = (count, ((seek + t) * 1e6).round() / 1e6); count += 1;
+        match on_frame(frame) { Ok(true) => {}, Ok(false) => break Ok(count), Err(e) => break Err(e) }
+    };
+    let _ = child.kill(); let _ = child.wait(); drop(pts_rx); let _ = log.join();
+    result
+}
+
+/// 解码 [start, end) 内的帧存入段内的 FrameStore；seek 为 ffmpeg 定位点
+fn decode_chunk(path: &Path, ffmpeg_path: &Path, info: VideoInfo, seek: f64, (start, end): (f64, f64), mut store: FrameStore, stop: &AtomicBool) -> Result<Vec<PyVideoFrame>> {
+    let mut cmd = Command::new(ffmpeg_path); cmd.args(["-hide_banner", "-nostats"]);
+    if seek > 0.0 { cmd.args(["-ss", &format!("{:.6}", seek)]); }
+    cmd.arg("-i").arg(path).args(GRAY_OUTPUT_ARGS);
+    let mut child = cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().context("Failed to spawn FFmpeg process")?;
+    read_gray_frames(&mut child, &mut PyVideoFrame::blank(info.width, info.height), seek, |frame| {
+        if stop.load(Ordering::Relaxed) || frame.timestamp >= end { return Ok(false); }
+        if frame.timestamp >= start { store.push(frame)?; }
+        Ok(true)
+    })?;
+    store.finish()
+}
+
+/// 把视频按时长切成至多 `workers` 段，由多个 ffmpeg 各自全速解码进段内的 FrameStore，再按顺序拼接回调。
+/// `ram_budget` 在各段间平分，超出部分落盘（None 不限）；拼接时帧像素以共享视图交出，不再复制。
+/// 帧号连续编号，时间戳取自 showinfo 的 pts，与单进程解码逐帧相同。
+/// 时长未知或过短时返回 None，由调用方退回单进程解码；内存输入先写入临时文件以便各进程定位
+pub fn stream_chunked(input: &VideoInput, ffmpeg_path: &Path, workers: usize, ram_budget: Option<usize>, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<Option<(VideoInfo, usize)>> {
+    let (path, _spilled) = input.as_file()?;
+    let probe = probe_text(&VideoInput::Path(path.clone()), ffmpeg_path)?;
+    let info = parse_probe_info(&probe)?;
+    let Some(duration) = parse_video_duration(&probe) else { return Ok(None) };
+    let n = workers.min((duration / MIN_CHUNK_SECS) as usize);
+    if n < 2 { return Ok(None); }
+    let bounds = chunk_bounds(duration, info.fps, n);
+    let ranges: Vec<(f64, f64)> = (0..n).map(|k| (if k == 0 { f64::NEG_INFINITY } else { bounds[k-1] }, bounds.get(k).copied().unwrap_or(f64::INFINITY))).collect();
+    let stop = AtomicBool::new(false);
+    let count = std::thread::scope(|s| -> Result<usize> {
+        let chunks: Vec<_> = ranges.iter().map(|&range| {
+            let (path, stop, store) = (&path, &stop, FrameStore::new(ram_budget.map(|b| b / n)));
+            // 按传给 -ss 的微秒精度取整，输出 pts 加回 seek 后才是原始时间戳
+            let seek = if range.0.is_finite() { ((range.0 - SEEK_MARGIN_FRAMES / info.fps).max(0.0) * 1e6).round() / 1e6 } else { 0.0 };
+            s.spawn(move || decode_chunk(path, ffmpeg_path, info, seek, range, store, stop))
+        }).collect();
+        // 各段互不等待；这里只按顺序拼接。提前结束或出错时通知其余段停止解码
+        let mut count = 0;
+        for handle in chunks {
+            let frames = match handle.join().map_err(|_| anyhow::anyhow!("Decoder thread panicked")).and_then(|r| r) {
+                Ok(frames) => frames,
+                Err(e) => { stop.store(true, Ordering::Relaxed); return Err(e); }
+            };
+            for mut frame in frames {
+                frame.frame_number = count;
+                let keep_going = on_frame(&frame); count += 1;
+                if !keep_going { stop.store(true, Ordering::Relaxed); return Ok(count); }
+            }
+        }
+        Ok(count)
+    })?;
+    Ok(Some((info, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::source::{FfmpegSource, FrameSource};
+    use std::path::PathBuf;
+
+    #[test]
+    fn chunk_bounds_put_every_frame_in_exactly_one_range() {
+        for (duration, fps, n) in [(10.0, 25.0, 4), (7.3, 29.97, 3), (60.0, 24000.0 / 1001.0, 8), (4.0, 30.0, 2)] {
+            let bounds = chunk_bounds(duration, fps, n);
+            assert_eq!(bounds.len(), n - 1);
+            assert!(bounds.windows(2).all(|w| w[0] < w[1]));
+            let edges: Vec<f64> = std::iter::once(f64::NEG_INFINITY).chain(bounds).chain(std::iter::once(f64::INFINITY)).collect();
+            for k in 0..(duration * fps).ceil() as usize {
+                let t = k as f64 / fps;
+                assert_eq!(edges.windows(2).filter(|r| r[0] <= t && t < r[1]).count(), 1, "frame {} at {}s ({}s @ {} fps, {} chunks)", k, t, duration, fps, n);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_showinfo_uses_time_base() {
+        let mut tb = None;
+        assert_eq!(parse_showinfo("[Parsed_showinfo_0 @ 0x55] config in time_base: 1/12800, frame_rate: 25/1", &mut tb), None);
+        assert_eq!(tb, Some(1.0 / 12800.0));
+        assert_eq!(parse_showinfo("[Parsed_showinfo_0 @ 0x55] n:   3 pts:   1536 pts_time:0.12    duration:    512", &mut tb), Some(0.12));
+        assert_eq!(parse_showinfo("frame=   10 fps=0.0 q=-0.0 size=N/A time=00:00:00.40", &mut tb), None);
+        assert_eq!(parse_showinfo("[Parsed_showinfo_0 @ 0x55] n:   4 pts:NOPTS pts_time:NOPTS", &mut tb), None);
+    }
+
+    #[test]
+    fn parse_showinfo_falls_back_to_pts_time() {
+        let mut tb = None;
+        assert_eq!(parse_showinfo("[Parsed_showinfo_0 @ 0x55] n:   1 pts:   1 pts_time:0.04", &mut tb), Some(0.04));
+        assert_eq!(parse_showinfo("[Parsed_showinfo_0 @ 0x55] config in time_base: 1/0", &mut tb), None);
+    }
+
+    fn decode(path: &Path, ffmpeg: &Path, workers: usize) -> Vec<(usize, f64, Vec<u8>)> {
+        let mut frames = Vec::new();
+        FfmpegSource { input: VideoInput::Path(path.to_path_buf()), ffmpeg_path: ffmpeg.to_path_buf(), workers, ram_budget: None }
+            .stream(&mut |f| { frames.push((f.frame_number, f.timestamp, f.pixels().to_vec())); true }).unwrap();
+        frames
+    }
+
+    /// 需要 ffmpeg（可用 INKFOX_TEST_FFMPEG 指定路径）；找不到时跳过
+    #[test]
+    fn chunked_decode_matches_serial() {
+        let ffmpeg = PathBuf::from(std::env::var_os("INKFOX_TEST_FFMPEG").unwrap_or_else(|| "ffmpeg".into()));
+        if Command::new(&ffmpeg).arg("-version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_err() { eprintln!("skipping: ffmpeg not found"); return; }
+        let dir = tempfile::tempdir().unwrap(); let clip = dir.path().join("clip.mkv");
+        let status = Command::new(&ffmpeg).args(["-hide_banner", "-loglevel", "error", "-f", "lavfi", "-i", "testsrc2=size=64x48:rate=25:duration=10", "-c:v", "ffv1", "-g", "1"])
+            .arg(&clip).status().unwrap();
+        assert!(status.success());
+        let serial = decode(&clip, &ffmpeg, 1);
+        assert_eq!(serial.len(), 250);
+        assert_eq!(decode(&clip, &ffmpeg, 4), serial);
+    }
+}
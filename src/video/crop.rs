@@ -0,0 +1,86 @@
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::mask::PyFrameMask;
+use crate::video::store::FrameData;
+
+/// 裁剪矩形（去除上下黑边 letterbox / 左右黑边 pillarbox 后的有效画面）
+#[pyclass]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyCropRect {
+    #[pyo3(get)] pub x: usize,
+    #[pyo3(get)] pub y: usize,
+    #[pyo3(get)] pub width: usize,
+    #[pyo3(get)] pub height: usize,
+}
+
+#[pymethods]
+impl PyCropRect {
+    #[new]
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self { Self { x, y, width, height } }
+    /// ffmpeg 滤镜写法：crop=w:h:x:y
+    pub fn to_ffmpeg_filter(&self) -> String { format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y) }
+    fn to_tuple(&self) -> (usize, usize, usize, usize) { (self.x, self.y, self.width, self.height) }
+    fn __repr__(&self) -> String { format!("PyCropRect(x={}, y={}, width={}, height={})", self.x, self.y, self.width, self.height) }
+}
+
+impl PyCropRect {
+    pub fn is_full_frame(&self, width: usize, height: usize) -> bool { self.x == 0 && self.y == 0 && self.width == width && self.height == height }
+    /// 裁剪区域转为掩码，用于分析阶段（不复制帧数据）
+    pub fn to_mask(&self, width: usize, height: usize) -> PyFrameMask { PyFrameMask::new(width, height, Some(vec![(self.x, self.y, self.width, self.height)]), None) }
+}
+
+#[derive(Debug, Clone)]
+pub struct CropParams {
+    /// 行/列平均亮度 <= limit 视为黑边（同 ffmpeg cropdetect 的 limit）
+    pub limit: f64,
+    /// 宽高取整到该值的倍数
+    pub round: usize,
+    /// 每隔多少帧采样一次
+    pub sample_step: usize,
+}
+
+impl Default for CropParams { fn default() -> Self { Self { limit: 24.0, round: 2, sample_step: 1 } } }
+
+/// 单帧有效区域 (top, bottom, left, right)，bottom/right 为开区间；整帧都是黑边时返回 None
+fn frame_bounds(frame: &PyVideoFrame, limit: f64) -> Option<(usize, usize, usize, usize)> {
+    let (w, h, p) = (frame.width, frame.height, frame.pixels());
+    if w == 0 || h == 0 { return None; }
+    let row_dark = |y: usize| p[y * w..(y + 1) * w].iter().map(|&v| v as u64).sum::<u64>() as f64 / w as f64 <= limit;
+    let col_dark = |x: usize, y0: usize, y1: usize| (y0..y1).map(|y| p[y * w + x] as u64).sum::<u64>() as f64 / (y1 - y0) as f64 <= limit;
+    let top = (0..h).find(|&y| !row_dark(y))?;
+    let bottom = (top..h).rev().find(|&y| !row_dark(y))? + 1;
+    let left = (0..w).find(|&x| !col_dark(x, top, bottom))?;
+    let right = (left..w).rev().find(|&x| !col_dark(x, top, bottom))? + 1;
+    Some((top, bottom, left, right))
+}
+
+fn quantile(values: &mut [usize], q: f64) -> usize {
+    values.sort_unstable();
+    values[((values.len() - 1) as f64 * q).round() as usize]
+}
+
+/// 在采样帧上检测稳定边框：取各边界的 10%/90% 分位，避免个别暗场景把画面裁掉
+pub fn detect_crop(frames: &[PyVideoFrame], params: &CropParams) -> Option<PyCropRect> {
+    let first = frames.first()?; let (w, h) = (first.width, first.height);
+    let bounds: Vec<(usize, usize, usize, usize)> = frames.par_iter().step_by(params.sample_step.max(1))
+        .filter(|f| f.width == w && f.height == h).filter_map(|f| frame_bounds(f, params.limit)).collect();
+    if bounds.is_empty() { return Some(PyCropRect::new(0, 0, w, h)); }
+    let pick = |sel: fn(&(usize, usize, usize, usize)) -> usize, q: f64| { let mut v: Vec<usize> = bounds.iter().map(sel).collect(); quantile(&mut v, q) };
+    let (top, bottom, left, right) = (pick(|b| b.0, 0.1), pick(|b| b.1, 0.9), pick(|b| b.2, 0.1), pick(|b| b.3, 0.9));
+    let round = params.round.max(1);
+    // 与 ffmpeg 相同：宽高向下取整，多出的像素均分到两侧
+    let (cw, ch) = ((right - left) / round * round, (bottom - top) / round * round);
+    if cw == 0 || ch == 0 { return Some(PyCropRect::new(0, 0, w, h)); }
+    Some(PyCropRect::new(left + (right - left - cw) / 2, top + (bottom - top - ch) / 2, cw, ch))
+}
+
+/// 返回裁剪后的新帧
+pub fn crop_frame(frame: &PyVideoFrame, rect: &PyCropRect) -> PyVideoFrame {
+    let (x, y) = (rect.x.min(frame.width), rect.y.min(frame.height));
+    let (cw, ch) = (rect.width.min(frame.width - x), rect.height.min(frame.height - y));
+    let p = frame.pixels(); let mut data = Vec::with_capacity(cw * ch);
+    for row in y..y + ch { data.extend_from_slice(&p[row * frame.width + x..row * frame.width + x + cw]); }
+    PyVideoFrame::from_data(frame.frame_number, cw, ch, FrameData::Owned(data), frame.timestamp)
+}
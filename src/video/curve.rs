@@ -0,0 +1,60 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::mask::PyFrameMask;
+use crate::video::utils::compute_frame_diffs;
+
+/// 完整的相邻帧差异曲线（列式存储），第 i 个点表示 frame_indices[i]-1 -> frame_indices[i] 的差异
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct PyDifferenceCurve {
+    #[pyo3(get)] pub frame_indices: Vec<usize>,
+    #[pyo3(get)] pub timestamps: Vec<f64>,
+    #[pyo3(get)] pub scores: Vec<f64>,
+}
+
+#[pymethods]
+impl PyDifferenceCurve {
+    fn __len__(&self) -> usize { self.scores.len() }
+    /// [(frame_index, timestamp, score), ...]
+    fn to_list(&self) -> Vec<(usize, f64, f64)> { (0..self.scores.len()).map(|i| (self.frame_indices[i], self.timestamps[i], self.scores[i])).collect() }
+    /// 行优先打包的 float64 (n, 3) 缓冲区（本机字节序），可直接 `np.frombuffer(buf).reshape(-1, 3)`
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let mut buf = Vec::with_capacity(self.scores.len() * 24);
+        for i in 0..self.scores.len() {
+            buf.extend_from_slice(&(self.frame_indices[i] as f64).to_ne_bytes());
+            buf.extend_from_slice(&self.timestamps[i].to_ne_bytes());
+            buf.extend_from_slice(&self.scores[i].to_ne_bytes());
+        }
+        PyBytes::new_bound(py, &buf)
+    }
+    fn __repr__(&self) -> String { format!("PyDifferenceCurve(points={})", self.scores.len()) }
+}
+
+/// 居中滑动平均（窗口两端自动收缩）
+pub fn smooth(values: &[f64], window: usize) -> Vec<f64> {
+    if window <= 1 || values.is_empty() { return values.to_vec(); }
+    let half = window / 2; let mut prefix = vec![0f64; values.len() + 1];
+    for (i, v) in values.iter().enumerate() { prefix[i + 1] = prefix[i] + v; }
+    (0..values.len()).map(|i| { let (lo, hi) = (i.saturating_sub(half), (i + half + 1).min(values.len())); (prefix[hi] - prefix[lo]) / (hi - lo) as f64 }).collect()
+}
+
+/// min-max 归一化到 [0,1]；常数曲线归一化为全 0
+pub fn normalize_min_max(values: &mut [f64]) {
+    let (min, max) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let range = max - min;
+    for v in values.iter_mut() { *v = if range > 0.0 { (*v - min) / range } else { 0.0 }; }
+}
+
+pub fn build_difference_curve(frames: &[PyVideoFrame], use_simd: bool, block_size: usize, smoothing: usize, normalize: bool, mask: Option<&PyFrameMask>) -> PyDifferenceCurve {
+    if frames.len() < 2 { return PyDifferenceCurve::default(); }
+    let diffs = compute_frame_diffs(frames, use_simd, block_size, mask);
+    let mut scores = smooth(&diffs.iter().map(|d| d.1).collect::<Vec<_>>(), smoothing);
+    if normalize { normalize_min_max(&mut scores); }
+    PyDifferenceCurve {
+        frame_indices: diffs.iter().map(|d| frames[d.0].frame_number).collect(),
+        timestamps: diffs.iter().map(|d| frames[d.0].timestamp).collect(),
+        scores,
+    }
+}
@@ -0,0 +1,141 @@
+use rayon::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::quality::histogram;
+
+/// 用于聚类的紧凑特征
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureKind { Thumbnail, Histogram, PHash }
+
+impl FeatureKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() { "thumbnail" | "thumb" => Some(Self::Thumbnail), "histogram" | "hist" => Some(Self::Histogram), "phash" => Some(Self::PHash), _ => None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiversityMethod { FarthestPoint, KMedoids }
+
+impl DiversityMethod {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() { "farthest" | "fps" | "farthest_point" => Some(Self::FarthestPoint), "kmedoids" | "k_medoids" => Some(Self::KMedoids), _ => None }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiversityParams {
+    pub feature: FeatureKind,
+    pub method: DiversityMethod,
+    /// 参与聚类的候选帧数量（按帧差取前 N），0 表示 max_keyframes * 4
+    pub candidates: usize,
+    /// 归一化距离低于该值的帧视为同一画面
+    pub min_distance: f64,
+}
+
+impl Default for DiversityParams {
+    fn default() -> Self { Self { feature: FeatureKind::Thumbnail, method: DiversityMethod::FarthestPoint, candidates: 0, min_distance: 0.02 } }
+}
+
+#[derive(Debug, Clone)]
+pub enum Feature { Vector(Vec<f32>), Hash(u64) }
+
+impl Feature {
+    /// 归一化距离 [0,1]
+    pub fn distance(&self, other: &Feature) -> f64 {
+        match (self, other) {
+            (Feature::Vector(a), Feature::Vector(b)) => a.iter().zip(b).map(|(x, y)| (x - y).abs() as f64).sum::<f64>() / a.len().max(1) as f64,
+            (Feature::Hash(a), Feature::Hash(b)) => (a ^ b).count_ones() as f64 / 64.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// 区域平均降采样到 size x size（结果为 0..=255 的浮点灰度）
+pub fn downsample(frame: &PyVideoFrame, size: usize) -> Vec<f32> {
+    let (w, h, pixels) = (frame.width, frame.height, frame.pixels());
+    let mut out = vec![0f32; size * size];
+    if w == 0 || h == 0 { return out; }
+    for ty in 0..size {
+        let (y0, y1) = (ty * h / size, ((ty + 1) * h / size).max(ty * h / size + 1).min(h));
+        for tx in 0..size {
+            let (x0, x1) = (tx * w / size, ((tx + 1) * w / size).max(tx * w / size + 1).min(w));
+            let mut sum = 0u64;
+            for y in y0..y1 { sum += pixels[y * w + x0..y * w + x1].iter().map(|&p| p as u64).sum::<u64>(); }
+            out[ty * size + tx] = sum as f32 / ((y1 - y0) * (x1 - x0)).max(1) as f32;
+        }
+    }
+    out
+}
+
+/// 感知哈希：32x32 缩略图 -> 二维 DCT -> 取左上 8x8 中除直流外的 63 个系数与其中位数比较（第 0 位恒为 0）
+pub fn phash(frame: &PyVideoFrame) -> u64 {
+    const N: usize = 32;
+    let img = downsample(frame, N);
+    let cos: Vec<f64> = (0..8 * N).map(|i| { let (u, x) = (i / N, i % N); (std::f64::consts::PI * (2 * x + 1) as f64 * u as f64 / (2 * N) as f64).cos() }).collect();
+    let mut coeffs = [0f64; 64];
+    for v in 0..8 { for u in 0..8 {
+        let mut s = 0f64;
+        for y in 0..N { for x in 0..N { s += img[y * N + x] as f64 * cos[u * N + x] * cos[v * N + y]; } }
+        coeffs[v * 8 + u] = s;
+    } }
+    let mut sorted = coeffs[1..].to_vec(); sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = sorted[sorted.len() / 2];
+    coeffs.iter().enumerate().skip(1).fold(0u64, |h, (i, c)| if *c > median { h | (1 << i) } else { h })
+}
+
+pub fn compute_feature(frame: &PyVideoFrame, kind: FeatureKind) -> Feature {
+    match kind {
+        FeatureKind::Thumbnail => Feature::Vector(downsample(frame, 16).into_iter().map(|v| v / 255.0).collect()),
+        FeatureKind::Histogram => {
+            let hist = histogram(frame.pixels()); let total = frame.pixels().len().max(1) as f32;
+            // 32 bin；L1 距离除以 bin 数后再乘 16，使完全不相交的两直方图距离为 1
+            Feature::Vector(hist.chunks(8).map(|c| c.iter().sum::<u64>() as f32 / total * 16.0).collect())
+        }
+        FeatureKind::PHash => Feature::Hash(phash(frame)),
+    }
+}
+
+/// 最远点采样：从 `seed` 开始，每次加入与已选集合最小距离最大的点；最大最小距离低于 `min_distance` 时提前停止
+pub fn farthest_point_sampling(dist: &[Vec<f64>], k: usize, seed: usize, min_distance: f64) -> Vec<usize> {
+    let n = dist.len(); if n == 0 || k == 0 { return vec![]; }
+    let mut selected = vec![seed]; let mut nearest: Vec<f64> = dist[seed].clone();
+    while selected.len() < k.min(n) {
+        let (best, best_d) = nearest.iter().enumerate().fold((0, f64::MIN), |acc, (i, &d)| if d > acc.1 { (i, d) } else { acc });
+        if best_d <= min_distance { break; }
+        selected.push(best);
+        for (i, d) in nearest.iter_mut().enumerate() { *d = d.min(dist[best][i]); }
+    }
+    selected
+}
+
+/// k-medoids（交替分配/更新），以最远点采样结果初始化
+pub fn k_medoids(dist: &[Vec<f64>], init: Vec<usize>, max_iter: usize) -> Vec<usize> {
+    let n = dist.len(); let mut medoids = init; if medoids.is_empty() { return medoids; }
+    for _ in 0..max_iter {
+        let assign: Vec<usize> = (0..n).map(|i| (0..medoids.len()).min_by(|&a, &b| dist[i][medoids[a]].partial_cmp(&dist[i][medoids[b]]).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(0)).collect();
+        let next: Vec<usize> = (0..medoids.len()).into_par_iter().map(|c| {
+            let members: Vec<usize> = (0..n).filter(|&i| assign[i] == c).collect();
+            members.iter().copied().min_by(|&a, &b| {
+                let ca: f64 = members.iter().map(|&m| dist[a][m]).sum(); let cb: f64 = members.iter().map(|&m| dist[b][m]).sum();
+                ca.partial_cmp(&cb).unwrap_or(std::cmp::Ordering::Equal)
+            }).unwrap_or(medoids[c])
+        }).collect();
+        if next == medoids { break; }
+        medoids = next;
+    }
+    medoids
+}
+
+/// 从候选帧中挑选至多 `k` 个视觉上互不相同的代表帧。
+/// `candidates` 为 (帧号, 差异分数)，分数最高者作为初始种子。
+pub fn select_diverse(frames: &[PyVideoFrame], candidates: &[(usize, f64)], k: usize, kind: FeatureKind, method: DiversityMethod, min_distance: f64) -> Vec<usize> {
+    if candidates.is_empty() || k == 0 { return vec![]; }
+    let features: Vec<Feature> = candidates.par_iter().map(|(i, _)| compute_feature(&frames[*i], kind)).collect();
+    let dist: Vec<Vec<f64>> = features.par_iter().map(|a| features.iter().map(|b| a.distance(b)).collect()).collect();
+    let seed = candidates.iter().enumerate().fold((0, f64::MIN), |acc, (i, c)| if c.1 > acc.1 { (i, c.1) } else { acc }).0;
+    let mut picked = farthest_point_sampling(&dist, k, seed, min_distance);
+    if method == DiversityMethod::KMedoids { picked = k_medoids(&dist, picked, 20); }
+    let mut out: Vec<usize> = picked.into_iter().map(|i| candidates[i].0).collect();
+    out.sort_unstable(); out.dedup();
+    out
+}
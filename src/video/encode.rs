@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::RgbImage;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::video::animation::{decode_rgb_frames, AnimationFormat};
+use crate::video::crop::PyCropRect;
+use crate::video::input::VideoInput;
+use crate::video::utils::probe_seekable;
+
+/// 编码后的关键帧（不落盘）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyEncodedKeyframe {
+    #[pyo3(get)] pub frame_index: usize,
+    #[pyo3(get)] pub timestamp: f64,
+    /// 与前一帧的平均像素差；调用方未提供时为 None
+    #[pyo3(get)] pub score: Option<f64>,
+    /// "jpeg" / "png" / "webp"
+    #[pyo3(get)] pub format: String,
+    #[pyo3(get)] pub width: u32,
+    #[pyo3(get)] pub height: u32,
+    pub data: Vec<u8>,
+    /// 仅在请求 base64 时填充
+    #[pyo3(get)] pub base64: Option<String>,
+}
+
+#[pymethods]
+impl PyEncodedKeyframe {
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> { PyBytes::new_bound(py, &self.data) }
+    #[getter]
+    fn mime_type(&self) -> String { format!("image/{}", self.format) }
+    /// data:image/...;base64,... 形式，可直接作为多模态模型的图片输入
+    fn data_uri(&self) -> String {
+        let b64 = self.base64.clone().unwrap_or_else(|| base64::engine::general_purpose::STANDARD.encode(&self.data));
+        format!("data:{};base64,{}", self.mime_type(), b64)
+    }
+    fn __len__(&self) -> usize { self.data.len() }
+    fn __repr__(&self) -> String { format!("PyEncodedKeyframe(frame_index={}, timestamp={:.3}, format='{}', {}x{}, {} bytes)", self.frame_index, self.timestamp, self.format, self.width, self.height, self.data.len()) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat { Jpeg, Png, WebP }
+
+impl ImageFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() { "jpeg" | "jpg" => Some(Self::Jpeg), "png" => Some(Self::Png), "webp" => Some(Self::WebP), _ => None }
+    }
+    pub fn name(&self) -> &'static str { match self { Self::Jpeg => "jpeg", Self::Png => "png", Self::WebP => "webp" } }
+    pub fn extension(&self) -> &'static str { match self { Self::Jpeg => "jpg", Self::Png => "png", Self::WebP => "webp" } }
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodeParams {
+    pub format: ImageFormat,
+    /// 长边上限（像素），0 表示不缩放
+    pub max_dimension: u32,
+    /// JPEG 质量 1..=100（PNG/WebP 为无损编码，忽略）
+    pub quality: u8,
+    /// 单张字节上限，0 表示不限；超出时先降低 JPEG 质量再逐步缩小尺寸
+    pub max_bytes: usize,
+    pub base64: bool,
+}
+
+impl Default for EncodeParams {
+    fn default() -> Self { Self { format: ImageFormat::Jpeg, max_dimension: 0, quality: 85, max_bytes: 0, base64: false } }
+}
+
+/// 按字节预算降质时 JPEG 质量下限与缩放时的最小长边
+const MIN_BUDGET_QUALITY: u8 = 30;
+const MIN_BUDGET_DIMENSION: u32 = 64;
+
+/// 解码输出尺寸：原尺寸、拉伸到指定尺寸，或等比缩小到长边不超过给定值（0 表示不缩放）
+#[derive(Debug, Clone, Copy)]
+pub enum DecodeSize { Native, Exact(u32, u32), Fit(u32) }
+
+impl DecodeSize {
+    /// 需要缩放时返回目标尺寸
+    fn resolve(self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let long = width.max(height);
+        match self {
+            Self::Exact(w, h) => Some((w, h)),
+            Self::Fit(max) if max > 0 && long > max => {
+                let scale = max as f64 / long as f64;
+                Some((((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn fit_dimension(img: RgbImage, max_dimension: u32) -> RgbImage {
+    match DecodeSize::Fit(max_dimension).resolve(img.width(), img.height()) {
+        Some((w, h)) => image::imageops::resize(&img, w, h, FilterType::Triangle),
+        None => img,
+    }
+}
+
+fn encode_once(img: &RgbImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut buf, quality.clamp(1, 100)).encode_image(img)?,
+        ImageFormat::Png => img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)?,
+        ImageFormat::WebP => img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::WebP)?,
+    }
+    Ok(buf)
+}
+
+/// 编码单帧，返回 (数据, 宽, 高)；预算无法满足时返回能达到的最小结果
+pub fn encode_image(img: RgbImage, params: &EncodeParams) -> Result<(Vec<u8>, u32, u32)> {
+    let mut img = fit_dimension(img, params.max_dimension);
+    loop {
+        let mut data = encode_once(&img, params.format, params.quality)?;
+        if params.max_bytes == 0 || data.len() <= params.max_bytes { return Ok((data, img.width(), img.height())); }
+        if params.format == ImageFormat::Jpeg && params.quality > MIN_BUDGET_QUALITY {
+            // 二分查找满足预算的最高质量
+            let (mut lo, mut hi, mut best) = (MIN_BUDGET_QUALITY, params.quality - 1, None);
+            while lo <= hi {
+                let q = lo + (hi - lo) / 2; let d = encode_once(&img, params.format, q)?;
+                if d.len() <= params.max_bytes { best = Some(d); lo = q + 1; } else { data = d; if q == 0 { break; } hi = q - 1; }
+            }
+            if let Some(d) = best { return Ok((d, img.width(), img.height())); }
+        }
+        let long = img.width().max(img.height());
+        if long <= MIN_BUDGET_DIMENSION { return Ok((data, img.width(), img.height())); }
+        img = fit_dimension(img, ((long as f64 * 0.75) as u32).max(MIN_BUDGET_DIMENSION));
+    }
+}
+
+/// 单次 ffmpeg 调用按帧号 select 取出 RGB 画面，裁剪与缩放都在 ffmpeg 滤镜中完成，不保留全分辨率画面；
+/// 按帧号升序返回 (帧号, 时间戳, 画面)。
+/// 内存输入经管道无法探测时（如 moov 在末尾的 MP4）会先写入 TMPDIR 下的临时文件，见 `probe_seekable`
+fn decode_rgb_frames_ffmpeg(input: &VideoInput, ffmpeg_path: &Path, wanted: &[usize], crop: Option<&PyCropRect>, size: DecodeSize) -> Result<Vec<(usize, f64, RgbImage)>> {
+    let (info, input, _spilled) = probe_seekable(input, ffmpeg_path)?;
+    let expr = wanted.iter().map(|i| format!("eq(n,{})", i)).collect::<Vec<_>>().join("+");
+    let (mut filter, (mut width, mut height)) = (format!("select='{}'", expr), (info.width as u32, info.height as u32));
+    if let Some(c) = crop {
+        // 与 crop_imm 一致：超出画面的部分截掉
+        let (x, y) = ((c.x as u32).min(width), (c.y as u32).min(height));
+        (width, height) = ((c.width as u32).min(width - x), (c.height as u32).min(height - y));
+        filter.push_str(&format!(",crop={}:{}:{}:{}", width, height, x, y));
+    }
+    if let Some((w, h)) = size.resolve(width, height) { filter.push_str(&format!(",scale={}:{}", w, h)); (width, height) = (w, h); }
+    let mut cmd = Command::new(ffmpeg_path); input.add_ffmpeg_input(&mut cmd);
+    cmd.args(["-vf", &filter, "-vsync", "0", "-an", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"]).stdout(Stdio::piped()).stderr(Stdio::null());
+    let mut child = cmd.spawn().context("Failed to spawn FFmpeg process")?; let writer = input.feed(&mut child);
+    let mut reader = BufReader::with_capacity(1024*1024, child.stdout.take().context("FFmpeg stdout unavailable")?);
+    let mut out = Vec::with_capacity(wanted.len());
+    for &idx in wanted {
+        let mut buf = vec![0u8; width as usize * height as usize * 3];
+        if reader.read_exact(&mut buf).is_err() { break; }
+        out.push((idx, idx as f64 / info.fps, RgbImage::from_raw(width, height, buf).context("Bad RGB frame size")?));
+    }
+    let _ = child.kill(); let _ = child.wait(); if let Some(w) = writer { let _ = w.join(); }
+    Ok(out)
+}
+
+/// 取出指定帧的 RGB 画面（先裁剪再缩放到 `size`）：动图在进程内解码，其余交给 ffmpeg
+pub fn decode_keyframe_images(input: &VideoInput, ffmpeg_path: &Path, indices: &[usize], crop: Option<&PyCropRect>, size: DecodeSize) -> Result<Vec<(usize, f64, RgbImage)>> {
+    let mut wanted = indices.to_vec(); wanted.sort_unstable(); wanted.dedup();
+    if wanted.is_empty() { return Ok(Vec::new()); }
+    match AnimationFormat::sniff(&input.head(12)) {
+        Some(format) => Ok(decode_rgb_frames(input, format, &wanted)?.into_iter().map(|(i, t, rgb)| {
+            let rgb = match crop { Some(c) => image::imageops::crop_imm(&rgb, c.x as u32, c.y as u32, c.width as u32, c.height as u32).to_image(), None => rgb };
+            match size.resolve(rgb.width(), rgb.height()) { Some((w, h)) => (i, t, image::imageops::resize(&rgb, w, h, FilterType::Triangle)), None => (i, t, rgb) }
+        }).collect()),
+        None => decode_rgb_frames_ffmpeg(input, ffmpeg_path, &wanted, crop, size),
+    }
+}
+
+/// 解码并编码关键帧，结果按帧号升序；`score_of` 给出每帧的分数。解码在调用线程上进行，只有编码在 `pool` 中并行
+pub fn encode_keyframes<S: Fn(usize) -> Option<f64> + Sync>(input: &VideoInput, ffmpeg_path: &Path, indices: &[usize], crop: Option<&PyCropRect>, params: &EncodeParams, pool: &ThreadPool, score_of: S) -> Result<Vec<PyEncodedKeyframe>> {
+    let decoded = decode_keyframe_images(input, ffmpeg_path, indices, crop, DecodeSize::Fit(params.max_dimension))?;
+    pool.install(|| decoded.into_par_iter().map(|(frame_index, timestamp, rgb)| {
+        let (data, width, height) = encode_image(rgb, params)?;
+        let base64 = params.base64.then(|| base64::engine::general_purpose::STANDARD.encode(&data));
+        Ok(PyEncodedKeyframe { frame_index, timestamp, score: score_of(frame_index), format: params.format.name().into(), width, height, data, base64 })
+    }).collect())
+}
@@ -1,53 +1,291 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::video::batch::{batch_output_dirs, run_batch, BatchJob, PyBatchResult};
+use crate::video::crop::{detect_crop, CropParams, PyCropRect};
+use crate::video::curve::{build_difference_curve, PyDifferenceCurve};
+use crate::video::diversity::{DiversityMethod, DiversityParams, FeatureKind};
+use crate::video::encode::{encode_keyframes, EncodeParams, ImageFormat, PyEncodedKeyframe};
 use crate::video::frame::PyVideoFrame;
+use crate::video::frozen::{detect_frozen_segments, FrozenParams, PyFrozenSegment};
+use crate::video::input::VideoInput;
+use crate::video::loops::{detect_loop, LoopParams, PyLoopInfo};
+use crate::video::mask::PyFrameMask;
+use crate::video::naming::{CollisionPolicy, NameTemplate, SaveOptions};
+use crate::video::slides::{select_slides, SlideParams};
+use crate::video::sprites::{generate_sprites, PySpriteSheet, SpriteParams, SpriteTimes};
+use crate::video::storyboard::{build_storyboard, PyStoryboard, StoryboardParams};
 use crate::video::performance::PyPerformanceResult;
+use crate::video::preview::{export_preview, PreviewFormat, PreviewParams};
+use crate::video::source::{FrameSource, SourceArg};
+use crate::video::stats::{collect_frame_stats, PyFrameStats, DEFAULT_EDGE_THRESHOLD};
+use crate::video::transition::{detect_transitions_in_frames, PyTransitionSegment, TransitionParams};
 use crate::video::utils::*;
 
+/// 每个提取器持有独立的 rayon 线程池，分析工作都在其中执行，不影响全局池和其他提取器；解码与保存（ffmpeg）在调用线程上进行
 #[pyclass]
-pub struct VideoKeyframeExtractor { ffmpeg_path:String, threads:usize, verbose:bool }
+pub struct VideoKeyframeExtractor { ffmpeg_path:String, pool:Arc<ThreadPool>, decode_workers:usize, ram_budget:Option<usize>, verbose:bool }
 
 #[pymethods]
 impl VideoKeyframeExtractor {
     #[new]
-    #[pyo3(signature = (ffmpeg_path = "ffmpeg".to_string(), threads = 0, verbose = false))]
-    pub fn new(ffmpeg_path:String, threads:usize, verbose:bool)->PyResult<Self> { if threads>0 { let _ = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global(); }
-        Ok(Self { ffmpeg_path, threads: if threads==0 { rayon::current_num_threads() } else { threads }, verbose }) }
-    #[pyo3(signature = (video_path, max_frames=None))]
-    pub fn extract_frames(&self, video_path:&str, max_frames:Option<usize>) -> PyResult<(Vec<PyVideoFrame>,usize,usize)> {
-        extract_frames_memory_stream(&PathBuf::from(video_path), &PathBuf::from(&self.ffmpeg_path), max_frames.unwrap_or(0), self.verbose)
+    /// decode_workers > 1 时把视频按时间分段，由多个 ffmpeg 进程并发解码；
+    /// ram_budget_mb 限制完整帧列表的常驻内存，超出部分落盘为 mmap 视图（None 不限）
+    #[pyo3(signature = (ffmpeg_path = "ffmpeg".to_string(), threads = 0, verbose = false, decode_workers = 1, ram_budget_mb = None))]
+    pub fn new(ffmpeg_path:String, threads:usize, verbose:bool, decode_workers:usize, ram_budget_mb:Option<usize>)->PyResult<Self> {
+        // threads=0 时按 rayon 默认（RAYON_NUM_THREADS 或 CPU 核数）
+        let pool = ThreadPoolBuilder::new().num_threads(threads).thread_name(|i| format!("inkfox-{}", i)).build()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Failed to create thread pool: {}", e)))?;
+        Ok(Self { ffmpeg_path, pool: Arc::new(pool), decode_workers: decode_workers.max(1), ram_budget: ram_budget_mb.map(|mb| mb * 1024 * 1024), verbose }) }
+    /// `video_path` 为路径或 PyFrameSource；max_frames 已弃用（总是提取全部帧）。ram_budget_mb 覆盖构造时的设置
+    #[pyo3(signature = (video_path, max_frames=None, ram_budget_mb=None))]
+    pub fn extract_frames(&self, py:Python<'_>, video_path:SourceArg, max_frames:Option<usize>, ram_budget_mb:Option<usize>) -> PyResult<(Vec<PyVideoFrame>,usize,usize)> {
+        let _ = max_frames;
+        let budget = ram_budget_mb.map(|mb| mb * 1024 * 1024).or(self.ram_budget);
+        collect_frames(self.open_source(py, &video_path)?.as_mut(), budget, self.verbose)
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Frame extraction failed: {}", e))) }
-    #[pyo3(signature = (frames, max_keyframes, use_simd=None, block_size=None))]
-    pub fn extract_keyframes(&self, frames:Vec<PyVideoFrame>, max_keyframes:usize, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<Vec<usize>> {
-        extract_keyframes_optimized(&frames, max_keyframes, use_simd.unwrap_or(true), block_size.unwrap_or(8192), self.verbose)
+    #[pyo3(signature = (frames, edge_threshold=None))]
+    pub fn frame_stats(&self, frames:Vec<PyVideoFrame>, edge_threshold:Option<u32>) -> PyFrameStats { self.pool.install(|| collect_frame_stats(&frames, edge_threshold.unwrap_or(DEFAULT_EDGE_THRESHOLD))) }
+    #[pyo3(signature = (video_path, edge_threshold=None))]
+    pub fn extract_frame_stats(&self, py:Python<'_>, video_path:SourceArg, edge_threshold:Option<u32>) -> PyResult<PyFrameStats> {
+        extract_frame_stats_stream(self.open_source(py, &video_path)?.as_mut(), edge_threshold.unwrap_or(DEFAULT_EDGE_THRESHOLD), &self.pool, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Frame stats extraction failed: {}", e))) }
+    #[pyo3(signature = (frames, max_keyframes, use_simd=None, block_size=None, exclude_blank=None, snap_transitions=None, refine_window=None, strategy=None, threshold=None, mask=None, crop=None, collapse_frozen=None, single_cycle=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn extract_keyframes(&self, frames:Vec<PyVideoFrame>, max_keyframes:usize, use_simd:Option<bool>, block_size:Option<usize>, exclude_blank:Option<bool>, snap_transitions:Option<bool>, refine_window:Option<usize>, strategy:Option<&str>, threshold:Option<f64>, mask:Option<&PyFrameMask>, crop:Option<PyCropRect>, collapse_frozen:Option<bool>, single_cycle:Option<bool>) -> PyResult<Vec<usize>> {
+        let opts = selection_options(strategy, threshold, exclude_blank, snap_transitions, refine_window, mask, crop, collapse_frozen, single_cycle)?;
+        self.pool.install(|| extract_keyframes_with_options(&frames, max_keyframes, use_simd.unwrap_or(true), block_size.unwrap_or(8192), &opts, self.verbose))
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Keyframe extraction failed: {}", e))) }
-    #[pyo3(signature = (video_path, keyframe_indices, output_dir, max_save=None))]
-    pub fn save_keyframes(&self, video_path:&str, keyframe_indices:Vec<usize>, output_dir:&str, max_save:Option<usize>) -> PyResult<usize> {
-        save_keyframes_optimized(&PathBuf::from(video_path), &keyframe_indices, &PathBuf::from(output_dir), &PathBuf::from(&self.ffmpeg_path), max_save.unwrap_or(50), self.verbose)
+    #[pyo3(signature = (frames, max_keyframes, feature=None, method=None, candidates=None, min_distance=None, use_simd=None, block_size=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn extract_keyframes_diverse(&self, frames:Vec<PyVideoFrame>, max_keyframes:usize, feature:Option<&str>, method:Option<&str>, candidates:Option<usize>, min_distance:Option<f64>, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<Vec<usize>> {
+        let params = diversity_params(feature, method, candidates, min_distance)?;
+        self.pool.install(|| extract_keyframes_diverse(&frames, max_keyframes, use_simd.unwrap_or(true), block_size.unwrap_or(8192), &params, self.verbose))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Diverse keyframe extraction failed: {}", e))) }
+    #[pyo3(signature = (frames, smoothing=None, normalize=None, use_simd=None, block_size=None, mask=None))]
+    pub fn difference_curve(&self, frames:Vec<PyVideoFrame>, smoothing:Option<usize>, normalize:Option<bool>, use_simd:Option<bool>, block_size:Option<usize>, mask:Option<&PyFrameMask>) -> PyResult<PyDifferenceCurve> {
+        if let (Some(m), Some(f)) = (mask, frames.first()) { m.check(f).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string()))?; }
+        Ok(self.pool.install(|| build_difference_curve(&frames, use_simd.unwrap_or(true), block_size.unwrap_or(8192), smoothing.unwrap_or(0), normalize.unwrap_or(false), mask))) }
+    #[pyo3(signature = (frames, max_keyframes=None, grid=None, change_threshold=None, cursor_size=None, new_slide_ratio=None, min_stable_frames=None, mask=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn extract_slide_keyframes(&self, frames:Vec<PyVideoFrame>, max_keyframes:Option<usize>, grid:Option<usize>, change_threshold:Option<f64>, cursor_size:Option<usize>, new_slide_ratio:Option<f64>, min_stable_frames:Option<usize>, mask:Option<&PyFrameMask>) -> PyResult<Vec<usize>> {
+        if let (Some(m), Some(f)) = (mask, frames.first()) { m.check(f).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string()))?; }
+        let d = SlideParams::default();
+        let params = SlideParams { grid: grid.unwrap_or(d.grid), change_threshold: change_threshold.unwrap_or(d.change_threshold), cursor_size: cursor_size.unwrap_or(d.cursor_size),
+            new_slide_ratio: new_slide_ratio.unwrap_or(d.new_slide_ratio), min_stable_frames: min_stable_frames.unwrap_or(d.min_stable_frames) };
+        self.pool.install(|| select_slides(&frames, max_keyframes.unwrap_or(usize::MAX), &params, mask))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string())) }
+    #[pyo3(signature = (frames, max_diff=None, min_period=None, max_period=None, min_cycles=None))]
+    pub fn detect_loop(&self, frames:Vec<PyVideoFrame>, max_diff:Option<f64>, min_period:Option<usize>, max_period:Option<usize>, min_cycles:Option<f64>) -> Option<PyLoopInfo> {
+        let d = LoopParams::default();
+        let params = LoopParams { max_diff: max_diff.unwrap_or(d.max_diff), min_period: min_period.unwrap_or(d.min_period), max_period: max_period.unwrap_or(d.max_period), min_cycles: min_cycles.unwrap_or(d.min_cycles), ..d };
+        self.pool.install(|| detect_loop(&frames, &params)) }
+    #[pyo3(signature = (frames, noise_tolerance=None, min_duration=None, use_simd=None, block_size=None, mask=None))]
+    pub fn detect_frozen_segments(&self, frames:Vec<PyVideoFrame>, noise_tolerance:Option<f64>, min_duration:Option<f64>, use_simd:Option<bool>, block_size:Option<usize>, mask:Option<&PyFrameMask>) -> PyResult<Vec<PyFrozenSegment>> {
+        if let (Some(m), Some(f)) = (mask, frames.first()) { m.check(f).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string()))?; }
+        let d = FrozenParams::default();
+        let params = FrozenParams { noise_tolerance: noise_tolerance.unwrap_or(d.noise_tolerance), min_duration: min_duration.unwrap_or(d.min_duration) };
+        Ok(self.pool.install(|| detect_frozen_segments(&frames, &params, use_simd.unwrap_or(true), block_size.unwrap_or(8192), mask))) }
+    #[pyo3(signature = (frames, black_threshold=None, white_threshold=None, blank_variance=None, min_fade_frames=None, min_dissolve_frames=None, use_simd=None, block_size=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn detect_transitions(&self, frames:Vec<PyVideoFrame>, black_threshold:Option<f64>, white_threshold:Option<f64>, blank_variance:Option<f64>, min_fade_frames:Option<usize>, min_dissolve_frames:Option<usize>, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<Vec<PyTransitionSegment>> {
+        let d = TransitionParams::default();
+        let params = TransitionParams { black_threshold: black_threshold.unwrap_or(d.black_threshold), white_threshold: white_threshold.unwrap_or(d.white_threshold),
+            blank_variance: blank_variance.unwrap_or(d.blank_variance), min_fade_frames: min_fade_frames.unwrap_or(d.min_fade_frames), min_dissolve_frames: min_dissolve_frames.unwrap_or(d.min_dissolve_frames) };
+        Ok(self.pool.install(|| detect_transitions_in_frames(&frames, use_simd.unwrap_or(true), block_size.unwrap_or(8192), &params))) }
+    #[pyo3(signature = (frames, limit=None, round=None, sample_step=None))]
+    pub fn detect_crop(&self, frames:Vec<PyVideoFrame>, limit:Option<f64>, round:Option<usize>, sample_step:Option<usize>) -> Option<PyCropRect> {
+        let d = CropParams::default();
+        let params = CropParams { limit: limit.unwrap_or(d.limit), round: round.unwrap_or(d.round), sample_step: sample_step.unwrap_or(d.sample_step) };
+        self.pool.install(|| detect_crop(&frames, &params)) }
+    /// template 占位符见 NameTemplate；scores 与 keyframe_indices 一一对应，仅在模板含 {score} 时需要。返回实际写出的数量
+    #[pyo3(signature = (video_path, keyframe_indices, output_dir, max_save=None, crop=None, template=None, format=None, quality=None, max_dimension=None, on_conflict=None, scores=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_keyframes(&self, py:Python<'_>, video_path:SourceArg, keyframe_indices:Vec<usize>, output_dir:PathBuf, max_save:Option<usize>, crop:Option<PyCropRect>, template:Option<&str>, format:Option<&str>, quality:Option<u8>, max_dimension:Option<u32>, on_conflict:Option<&str>, scores:Option<Vec<f64>>) -> PyResult<usize> {
+        let save = save_options(template, format, quality, max_dimension, on_conflict)?;
+        match &scores {
+            Some(s) if s.len() != keyframe_indices.len() => return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("scores must have the same length as keyframe_indices")),
+            None if save.template.uses_score() => return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("template uses {score} but no scores were given")),
+            _ => {}
+        }
+        let input = video_path.input(py)?;
+        save_keyframes_optimized(&input, &keyframe_indices, scores.as_deref(), &output_dir, self.ffmpeg_path.as_ref(), max_save.unwrap_or(50), crop.as_ref(), &save, self.verbose)
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Save keyframes failed: {}", e))) }
+    /// 在内存中编码指定帧（不写文件）；scores 与 keyframe_indices 一一对应，结果按帧号升序
+    #[pyo3(signature = (video_path, keyframe_indices, format=None, max_dimension=None, quality=None, max_bytes=None, base64=None, crop=None, scores=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode_keyframes(&self, py:Python<'_>, video_path:SourceArg, keyframe_indices:Vec<usize>, format:Option<&str>, max_dimension:Option<u32>, quality:Option<u8>, max_bytes:Option<usize>, base64:Option<bool>, crop:Option<PyCropRect>, scores:Option<Vec<f64>>) -> PyResult<Vec<PyEncodedKeyframe>> {
+        let params = encode_params(format, max_dimension, quality, max_bytes, base64)?;
+        if scores.as_ref().is_some_and(|s| s.len() != keyframe_indices.len()) { return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("scores must have the same length as keyframe_indices")); }
+        let score_map: HashMap<usize,f64> = scores.map(|s| keyframe_indices.iter().copied().zip(s).collect()).unwrap_or_default();
+        let input = video_path.input(py)?;
+        encode_keyframes(&input, self.ffmpeg_path.as_ref(), &keyframe_indices, crop.as_ref(), &params, &self.pool, |i| score_map.get(&i).copied())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Encode keyframes failed: {}", e))) }
+    /// 与 process_video 相同的选帧流程，但关键帧以编码后的图片返回；score 为该帧与前一帧的差异
+    #[pyo3(signature = (video_path, max_keyframes, format=None, max_dimension=None, quality=None, max_bytes=None, base64=None, use_simd=None, block_size=None, exclude_blank=None, snap_transitions=None, refine_window=None, strategy=None, threshold=None, mask=None, auto_crop=None, collapse_frozen=None, single_cycle=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_video_encoded(&self, py:Python<'_>, video_path:SourceArg, max_keyframes:usize, format:Option<&str>, max_dimension:Option<u32>, quality:Option<u8>, max_bytes:Option<usize>, base64:Option<bool>, use_simd:Option<bool>, block_size:Option<usize>, exclude_blank:Option<bool>, snap_transitions:Option<bool>, refine_window:Option<usize>, strategy:Option<&str>, threshold:Option<f64>, mask:Option<&PyFrameMask>, auto_crop:Option<bool>, collapse_frozen:Option<bool>, single_cycle:Option<bool>) -> PyResult<Vec<PyEncodedKeyframe>> {
+        let params = encode_params(format, max_dimension, quality, max_bytes, base64)?;
+        let mut opts = selection_options(strategy, threshold, exclude_blank, snap_transitions, refine_window, mask, None, collapse_frozen, single_cycle)?;
+        let (input, ffmpeg_path) = (video_path.input(py)?, Path::new(&self.ffmpeg_path));
+        let (use_simd, block) = (use_simd.unwrap_or(true), block_size.unwrap_or(8192));
+        let (frames,_,_) = extract_frames_memory_stream(&input, ffmpeg_path, 0, self.decode_workers, self.ram_budget, &self.pool, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Frame extraction failed: {}", e)))?;
+        let (keyframes, scores) = self.select_keyframes(&frames, max_keyframes, use_simd, block, &mut opts, auto_crop.unwrap_or(false), true)?; drop(frames);
+        let score_map: HashMap<usize,f64> = keyframes.iter().copied().zip(scores.unwrap_or_default()).collect();
+        encode_keyframes(&input, ffmpeg_path, &keyframes, opts.crop.as_ref(), &params, &self.pool, |i| score_map.get(&i).copied())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Encode keyframes failed: {}", e))) }
+    /// 把关键帧按网格拼成一张故事板图，可选在每格左下角标注时间码
+    #[pyo3(signature = (video_path, keyframe_indices, columns=None, tile_width=None, tile_height=None, padding=None, timecodes=None, format=None, quality=None, crop=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_storyboard(&self, py:Python<'_>, video_path:SourceArg, keyframe_indices:Vec<usize>, columns:Option<usize>, tile_width:Option<u32>, tile_height:Option<u32>, padding:Option<u32>, timecodes:Option<bool>, format:Option<&str>, quality:Option<u8>, crop:Option<PyCropRect>) -> PyResult<PyStoryboard> {
+        let d = StoryboardParams::default();
+        let params = StoryboardParams { columns: columns.unwrap_or(d.columns), tile_width: tile_width.unwrap_or(d.tile_width), tile_height: tile_height.unwrap_or(d.tile_height), padding: padding.unwrap_or(d.padding),
+            timecodes: timecodes.unwrap_or(d.timecodes), encode: encode_params(format, None, quality, None, None)?, ..d };
+        let input = video_path.input(py)?;
+        build_storyboard(&input, self.ffmpeg_path.as_ref(), &keyframe_indices, crop.as_ref(), &params, &self.pool)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Build storyboard failed: {}", e))) }
+    /// 生成播放器拖动预览：雪碧图 + WebVTT（`#xywh` 区域）。按固定间隔（秒，默认 10）或指定关键帧取样，二者只能选一
+    #[pyo3(signature = (video_path, output_dir, interval=None, keyframe_indices=None, tile_width=None, tile_height=None, columns=None, rows=None, prefix=None, url_prefix=None, format=None, quality=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_sprites(&self, py:Python<'_>, video_path:SourceArg, output_dir:PathBuf, interval:Option<f64>, keyframe_indices:Option<Vec<usize>>, tile_width:Option<u32>, tile_height:Option<u32>, columns:Option<usize>, rows:Option<usize>, prefix:Option<String>, url_prefix:Option<String>, format:Option<&str>, quality:Option<u8>) -> PyResult<PySpriteSheet> {
+        let times = match (interval, keyframe_indices) {
+            (Some(_), Some(_)) => return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("interval and keyframe_indices are mutually exclusive")),
+            (_, Some(indices)) => SpriteTimes::Frames(indices),
+            (Some(secs), None) if secs <= 0.0 => return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("interval must be positive")),
+            (secs, None) => SpriteTimes::Interval(secs.unwrap_or(10.0)),
+        };
+        let d = SpriteParams::default();
+        let params = SpriteParams { tile_width: tile_width.unwrap_or(d.tile_width), tile_height: tile_height.unwrap_or(d.tile_height), columns: columns.unwrap_or(d.columns), rows: rows.unwrap_or(d.rows),
+            prefix: prefix.unwrap_or(d.prefix), url_prefix: url_prefix.unwrap_or(d.url_prefix), encode: encode_params(format, None, quality, None, None)? };
+        let input = video_path.input(py)?;
+        generate_sprites(&input, self.ffmpeg_path.as_ref(), &output_dir, &times, &params, self.decode_workers, &self.pool, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Sprite generation failed: {}", e))) }
+    /// 把关键帧（window=0）或其前后 window 秒的片段导出为单个 GIF/WebP/MP4，返回预览时长（秒）。format 缺省时按扩展名推断
+    #[pyo3(signature = (video_path, keyframe_indices, output_path, format=None, window=None, hold=None, fps=None, width=None, loop_count=None, quality=None, crop=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_preview(&self, py:Python<'_>, video_path:SourceArg, keyframe_indices:Vec<usize>, output_path:PathBuf, format:Option<&str>, window:Option<f64>, hold:Option<f64>, fps:Option<f64>, width:Option<u32>, loop_count:Option<i32>, quality:Option<u8>, crop:Option<PyCropRect>) -> PyResult<f64> {
+        let format = match format { Some(f) => PreviewFormat::parse(f), None => PreviewFormat::from_path(&output_path) }
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown preview format '{}', expected gif/webp/mp4", format.map_or_else(|| output_path.display().to_string(), str::to_string))))?;
+        let d = PreviewParams::default();
+        let params = PreviewParams { format, window: window.unwrap_or(d.window).max(0.0), hold: hold.unwrap_or(d.hold), fps: fps.unwrap_or(d.fps), width: width.unwrap_or(d.width), loop_count: loop_count.unwrap_or(d.loop_count), quality: quality.unwrap_or(d.quality) };
+        if params.hold <= 0.0 || params.fps <= 0.0 { return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("hold and fps must be positive")); }
+        export_preview(&video_path.input(py)?, self.ffmpeg_path.as_ref(), &keyframe_indices, &output_path, crop.as_ref(), &params)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Preview export failed: {}", e))) }
     #[pyo3(signature = (video_path, max_keyframes, test_name, use_simd=None, block_size=None))]
-    pub fn benchmark(&self, video_path:&str, max_keyframes:usize, test_name:&str, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<PyPerformanceResult> {
-        run_performance_test(&PathBuf::from(video_path), max_keyframes, test_name, &PathBuf::from(&self.ffmpeg_path), use_simd.unwrap_or(true), block_size.unwrap_or(8192), self.verbose)
+    pub fn benchmark(&self, py:Python<'_>, video_path:SourceArg, max_keyframes:usize, test_name:&str, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<PyPerformanceResult> {
+        let input = video_path.input(py)?;
+        run_performance_test(&input, max_keyframes, test_name, self.ffmpeg_path.as_ref(), use_simd.unwrap_or(true), block_size.unwrap_or(8192), self.decode_workers, self.ram_budget, &self.pool, self.verbose)
             .map(|r| r.into()).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Benchmark failed: {}", e))) }
-    #[pyo3(signature = (video_path, output_dir, max_keyframes, max_save=None, use_simd=None, block_size=None))]
-    pub fn process_video(&self, video_path:&str, output_dir:&str, max_keyframes:usize, max_save:Option<usize>, use_simd:Option<bool>, block_size:Option<usize>) -> PyResult<PyPerformanceResult> {
-        let max_save_val = max_save.unwrap_or(50); let use_simd_val = use_simd.unwrap_or(true); let block = block_size.unwrap_or(8192);
-        let video_path_buf = PathBuf::from(video_path); let output_dir_buf = PathBuf::from(output_dir);
-        let result = run_performance_test(&video_path_buf, max_keyframes, "Python Processing", &PathBuf::from(&self.ffmpeg_path), use_simd_val, block, self.verbose)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Processing failed: {}", e)))?;
-        let (frames,_,_) = extract_frames_memory_stream(&video_path_buf, &PathBuf::from(&self.ffmpeg_path), 0, self.verbose)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Frame extraction failed: {}", e)))?;
-        let keyframes = extract_keyframes_optimized(&frames, max_keyframes, use_simd_val, block, self.verbose)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Keyframe extraction failed: {}", e)))?;
-        save_keyframes_optimized(&video_path_buf, &keyframes, &output_dir_buf, &PathBuf::from(&self.ffmpeg_path), max_save_val, self.verbose)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Save keyframes failed: {}", e)))?; Ok(result.into()) }
+    #[pyo3(signature = (video_path, output_dir, max_keyframes, max_save=None, use_simd=None, block_size=None, exclude_blank=None, snap_transitions=None, refine_window=None, strategy=None, threshold=None, mask=None, auto_crop=None, collapse_frozen=None, single_cycle=None, template=None, format=None, quality=None, max_dimension=None, on_conflict=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_video(&self, py:Python<'_>, video_path:SourceArg, output_dir:PathBuf, max_keyframes:usize, max_save:Option<usize>, use_simd:Option<bool>, block_size:Option<usize>, exclude_blank:Option<bool>, snap_transitions:Option<bool>, refine_window:Option<usize>, strategy:Option<&str>, threshold:Option<f64>, mask:Option<&PyFrameMask>, auto_crop:Option<bool>, collapse_frozen:Option<bool>, single_cycle:Option<bool>, template:Option<&str>, format:Option<&str>, quality:Option<u8>, max_dimension:Option<u32>, on_conflict:Option<&str>) -> PyResult<PyPerformanceResult> {
+        let opts = selection_options(strategy, threshold, exclude_blank, snap_transitions, refine_window, mask, None, collapse_frozen, single_cycle)?;
+        let save = save_options(template, format, quality, max_dimension, on_conflict)?;
+        self.process_video_with_options(&video_path.input(py)?, &output_dir, max_keyframes, max_save, use_simd, block_size, opts, &save, auto_crop.unwrap_or(false)) }
+    /// 批量处理多个视频：每个视频只解码一次，关键帧保存到 output_dir/<文件名> 下；至多 max_concurrent 个视频同时解码（默认 2），
+    /// 分析阶段共用线程池。单个视频失败记录在对应条目中，不影响其余视频；其余参数与 process_video 相同
+    #[pyo3(signature = (paths, output_dir, max_keyframes, max_concurrent=None, max_save=None, use_simd=None, block_size=None, exclude_blank=None, snap_transitions=None, refine_window=None, strategy=None, threshold=None, mask=None, auto_crop=None, collapse_frozen=None, single_cycle=None, template=None, format=None, quality=None, max_dimension=None, on_conflict=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_videos(&self, py:Python<'_>, paths:Vec<SourceArg>, output_dir:PathBuf, max_keyframes:usize, max_concurrent:Option<usize>, max_save:Option<usize>, use_simd:Option<bool>, block_size:Option<usize>, exclude_blank:Option<bool>, snap_transitions:Option<bool>, refine_window:Option<usize>, strategy:Option<&str>, threshold:Option<f64>, mask:Option<&PyFrameMask>, auto_crop:Option<bool>, collapse_frozen:Option<bool>, single_cycle:Option<bool>, template:Option<&str>, format:Option<&str>, quality:Option<u8>, max_dimension:Option<u32>, on_conflict:Option<&str>) -> PyResult<PyBatchResult> {
+        let opts = selection_options(strategy, threshold, exclude_blank, snap_transitions, refine_window, mask, None, collapse_frozen, single_cycle)?;
+        let save = save_options(template, format, quality, max_dimension, on_conflict)?;
+        if max_concurrent == Some(0) { return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("max_concurrent must be at least 1")); }
+        let inputs: Vec<Result<VideoInput, String>> = paths.iter().map(|p| p.input(py).map_err(|e| e.to_string())).collect();
+        let stems: Vec<String> = inputs.iter().enumerate().map(|(k, i)| i.as_ref().map(|i| i.stem()).unwrap_or_else(|_| format!("video_{}", k))).collect();
+        let jobs = inputs.into_iter().zip(batch_output_dirs(&output_dir, &stems)).enumerate().map(|(k, (input, dir))| {
+            let name = input.as_ref().map(|i| i.display_name()).unwrap_or_else(|_| format!("<video {}>", k));
+            BatchJob { name, output_dir: dir, input }
+        }).collect();
+        let auto_crop = auto_crop.unwrap_or(false);
+        // 工作线程不持有 GIL；错误转为字符串时才短暂获取
+        Ok(py.allow_threads(|| run_batch(jobs, max_concurrent.unwrap_or(2), max_keyframes, self.pool.current_num_threads(), self.verbose, |input, dir| {
+            self.process_video_with_options(input, dir, max_keyframes, max_save, use_simd, block_size, opts.clone(), &save, auto_crop).map_err(|e| e.to_string())
+        }))) }
     pub fn get_cpu_features(&self) -> PyResult<HashMap<String,bool>> { let mut f = HashMap::new(); #[cfg(target_arch="x86_64")] {
         f.insert("avx2".into(), std::arch::is_x86_feature_detected!("avx2")); f.insert("sse2".into(), std::arch::is_x86_feature_detected!("sse2"));
         f.insert("sse4_1".into(), std::arch::is_x86_feature_detected!("sse4.1")); f.insert("sse4_2".into(), std::arch::is_x86_feature_detected!("sse4.2")); f.insert("fma".into(), std::arch::is_x86_feature_detected!("fma")); }
         #[cfg(not(target_arch="x86_64"))] { f.insert("simd_supported".into(), false); } Ok(f) }
-    pub fn get_thread_count(&self) -> usize { self.threads }
-    pub fn get_configured_threads(&self) -> usize { self.threads }
-    pub fn get_actual_thread_count(&self) -> usize { rayon::current_num_threads() }
-}
\ No newline at end of file
+    pub fn get_thread_count(&self) -> usize { self.pool.current_num_threads() }
+    pub fn get_configured_threads(&self) -> usize { self.pool.current_num_threads() }
+    /// 实际执行分析的线程数：在本提取器的线程池中查询
+    pub fn get_actual_thread_count(&self) -> usize { self.pool.install(rayon::current_num_threads) }
+}
+
+fn diversity_params(feature:Option<&str>, method:Option<&str>, candidates:Option<usize>, min_distance:Option<f64>) -> PyResult<DiversityParams> {
+    let d = DiversityParams::default();
+    let feature = match feature { Some(f) => FeatureKind::parse(f).ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown feature '{}', expected thumbnail/histogram/phash", f)))?, None => d.feature };
+    let method = match method { Some(m) => DiversityMethod::parse(m).ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown method '{}', expected farthest/kmedoids", m)))?, None => d.method };
+    Ok(DiversityParams { feature, method, candidates: candidates.unwrap_or(d.candidates), min_distance: min_distance.unwrap_or(d.min_distance) })
+}
+
+fn encode_params(format:Option<&str>, max_dimension:Option<u32>, quality:Option<u8>, max_bytes:Option<usize>, base64:Option<bool>) -> PyResult<EncodeParams> {
+    let d = EncodeParams::default();
+    let format = match format { Some(f) => ImageFormat::parse(f).ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown image format '{}', expected jpeg/png/webp", f)))?, None => d.format };
+    if quality.is_some_and(|q| q == 0 || q > 100) { return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("quality must be in 1..=100")); }
+    Ok(EncodeParams { format, max_dimension: max_dimension.unwrap_or(d.max_dimension), quality: quality.unwrap_or(d.quality), max_bytes: max_bytes.unwrap_or(d.max_bytes), base64: base64.unwrap_or(d.base64) })
+}
+
+fn save_options(template:Option<&str>, format:Option<&str>, quality:Option<u8>, max_dimension:Option<u32>, on_conflict:Option<&str>) -> PyResult<SaveOptions> {
+    let d = SaveOptions::default();
+    let template = match template { Some(t) => NameTemplate::parse(t).map_err(PyErr::new::<pyo3::exceptions::PyValueError,_>)?, None => d.template };
+    let format = match format { Some(f) => ImageFormat::parse(f).ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown image format '{}', expected jpeg/png/webp", f)))?, None => d.format };
+    let collision = match on_conflict { Some(c) => CollisionPolicy::parse(c).ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown on_conflict '{}', expected overwrite/skip/unique", c)))?, None => d.collision };
+    if quality.is_some_and(|q| q == 0 || q > 100) { return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>("quality must be in 1..=100")); }
+    Ok(SaveOptions { template, format, quality: quality.unwrap_or(d.quality), max_dimension: max_dimension.unwrap_or(d.max_dimension), collision })
+}
+
+impl VideoKeyframeExtractor {
+    /// process_video 的 Rust 侧入口，供批处理与便捷函数直接传入已解析的选项。
+    /// 只解码、选帧各一次，结果取实际选出的关键帧数和各阶段耗时（总耗时含保存）
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_video_with_options(&self, input:&VideoInput, output_dir:&Path, max_keyframes:usize, max_save:Option<usize>, use_simd:Option<bool>, block_size:Option<usize>, mut opts:SelectionOptions, save:&SaveOptions, auto_crop:bool) -> PyResult<PyPerformanceResult> {
+        let (use_simd, block) = (use_simd.unwrap_or(true), block_size.unwrap_or(8192)); let ffmpeg_path = Path::new(&self.ffmpeg_path);
+        let start = Instant::now();
+        let (frames,_,_) = extract_frames_memory_stream(input, ffmpeg_path, 0, self.decode_workers, self.ram_budget, &self.pool, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Frame extraction failed: {}", e)))?;
+        let extraction_time = start.elapsed().as_secs_f64()*1000.0; let analysis_start = Instant::now();
+        let (keyframes, scores) = self.select_keyframes(&frames, max_keyframes, use_simd, block, &mut opts, auto_crop, save.template.uses_score())?;
+        let analysis_time = analysis_start.elapsed().as_secs_f64()*1000.0; let total_frames = frames.len(); drop(frames);
+        save_keyframes_optimized(input, &keyframes, scores.as_deref(), output_dir, ffmpeg_path, max_save.unwrap_or(50), opts.crop.as_ref(), save, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Save keyframes failed: {}", e)))?;
+        let total_time = start.elapsed().as_secs_f64()*1000.0;
+        Ok(PyPerformanceResult { test_name: "Python Processing".into(), video_file: input.display_name(), total_time_ms: total_time, frame_extraction_time_ms: extraction_time, keyframe_analysis_time_ms: analysis_time,
+            total_frames, keyframes_extracted: keyframes.len(), keyframe_ratio: if total_frames > 0 { keyframes.len() as f64 / total_frames as f64 * 100.0 } else { 0.0 },
+            processing_fps: total_frames as f64 / (total_time/1000.0), max_keyframes_requested: max_keyframes,
+            optimization_type: if use_simd { format!("SIMD+Parallel(block:{})", block) } else { "Standard Parallel".into() }, simd_enabled: use_simd,
+            threads_used: self.pool.current_num_threads(), timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string() }) }
+
+    /// 选帧（含自动裁边，以及 {score} 所需的帧差），在本提取器的线程池中执行；返回 (关键帧, 分数)
+    #[allow(clippy::too_many_arguments)]
+    fn select_keyframes(&self, frames:&[PyVideoFrame], max_keyframes:usize, use_simd:bool, block:usize, opts:&mut SelectionOptions, auto_crop:bool, with_scores:bool) -> PyResult<(Vec<usize>, Option<Vec<f64>>)> { self.pool.install(|| {
+        if auto_crop { opts.crop = detect_crop(frames, &CropParams::default()).filter(|c| !c.is_full_frame(frames[0].width, frames[0].height)); }
+        let keyframes = extract_keyframes_with_options(frames, max_keyframes, use_simd, block, opts, self.verbose)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError,_>(format!("Keyframe extraction failed: {}", e)))?;
+        // {score} 使用该帧与前一帧的差异
+        let scores = with_scores.then(|| { let curve = diff_curve(&compute_frame_diffs(frames, use_simd, block, opts.mask.as_ref()), frames.len()); keyframes.iter().map(|&i| curve[i]).collect::<Vec<f64>>() });
+        Ok((keyframes, scores)) }) }
+
+    /// 打开帧来源；来源在调用线程上解码，其内部并行改用本提取器的线程池
+    fn open_source<'py>(&self, py:Python<'py>, video_path:&SourceArg) -> PyResult<Box<dyn FrameSource + 'py>> {
+        let mut source = video_path.open(py, self.ffmpeg_path.as_ref(), self.decode_workers)?; source.use_thread_pool(&self.pool); Ok(source) }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn selection_options(strategy:Option<&str>, threshold:Option<f64>, exclude_blank:Option<bool>, snap_transitions:Option<bool>, refine_window:Option<usize>, mask:Option<&PyFrameMask>, crop:Option<PyCropRect>, collapse_frozen:Option<bool>, single_cycle:Option<bool>) -> PyResult<SelectionOptions> {
+    let strategy = match strategy {
+        Some(name) => SelectionStrategy::parse(name, threshold, DiversityParams::default())
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("Unknown strategy '{}', expected top_k/threshold/uniform/segment_quota/diverse/slides", name)))?,
+        None => match threshold { Some(t) => SelectionStrategy::Threshold(t), None => SelectionStrategy::TopK },
+    };
+    Ok(SelectionOptions { strategy, exclude_blank: exclude_blank.unwrap_or(false), snap_to_transition_end: snap_transitions.unwrap_or(false), refine_window: refine_window.unwrap_or(0), mask: mask.cloned(), crop,
+        collapse_frozen: if collapse_frozen.unwrap_or(false) { Some(FrozenParams::default()) } else { None },
+        single_cycle: if single_cycle.unwrap_or(false) { Some(LoopParams::default()) } else { None }, ..Default::default() })
+}
@@ -1,4 +1,16 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::video::crop::{crop_frame, PyCropRect};
+use crate::video::mask::PyFrameMask;
+use crate::video::quality;
+use crate::video::store::FrameData;
+
+/// 无法探测帧率时使用的默认值
+pub const DEFAULT_FPS: f64 = 30.0;
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
@@ -12,33 +24,82 @@ pub struct PyVideoFrame {
     pub width: usize,
     #[pyo3(get)]
     pub height: usize,
-    pub data: Vec<u8>,
+    /// 显示时间（秒）；未知帧率时按 DEFAULT_FPS 推算
+    #[pyo3(get)]
+    pub timestamp: f64,
+    /// 长度恰为 width*height，不做对齐填充（SIMD 内核以标量处理尾部）
+    pub data: FrameData,
 }
 
 #[pymethods]
 impl PyVideoFrame {
     #[new]
-    pub fn new(frame_number: usize, width: usize, height: usize, data: Vec<u8>) -> Self {
-        let mut aligned_data = data;
-        let remainder = aligned_data.len() % 32;
-        if remainder != 0 { aligned_data.resize(aligned_data.len() + (32 - remainder), 0); }
-        Self { frame_number, width, height, data: aligned_data }
+    #[pyo3(signature = (frame_number, width, height, data, timestamp=None))]
+    pub fn new(frame_number: usize, width: usize, height: usize, data: Vec<u8>, timestamp: Option<f64>) -> PyResult<Self> {
+        // pixels() 按 width*height 取数据，不足时拒绝而不是补零
+        if data.len() < width * height {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError,_>(format!("data has {} bytes but a {}x{} frame needs {}", data.len(), width, height, width * height)));
+        }
+        let timestamp = timestamp.unwrap_or(frame_number as f64 / DEFAULT_FPS);
+        Ok(Self { frame_number, width, height, timestamp, data: FrameData::Owned(data) })
     }
     fn get_data(&self) -> &[u8] { let pixel_count = self.width * self.height; &self.data[..pixel_count] }
-    pub fn calculate_difference(&self, other: &PyVideoFrame) -> PyResult<f64> {
+    /// 像素是否位于落盘的 mmap 文件中（超出内存预算时）
+    #[getter]
+    fn spilled(&self) -> bool { self.data.is_mapped() }
+    #[pyo3(signature = (other, mask=None))]
+    pub fn calculate_difference(&self, other: &PyVideoFrame, mask: Option<&PyFrameMask>) -> PyResult<f64> {
         if self.width != other.width || self.height != other.height { return Ok(f64::MAX); }
+        if let Some(m) = mask {
+            m.check(self).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string()))?;
+            return Ok(self.calculate_difference_masked(other, m, usize::MAX, false));
+        }
         let total_pixels = self.width * self.height;
         let total_diff: u64 = self.data[..total_pixels].iter().zip(other.data[..total_pixels].iter())
             .map(|(a,b)| (*a as i32 - *b as i32).abs() as u64).sum();
         Ok(total_diff as f64 / total_pixels as f64)
     }
-    #[pyo3(signature = (other, block_size=None))]
-    fn calculate_difference_simd(&self, other: &PyVideoFrame, block_size: Option<usize>) -> PyResult<f64> {
+    #[pyo3(signature = (other, block_size=None, mask=None))]
+    fn calculate_difference_simd(&self, other: &PyVideoFrame, block_size: Option<usize>, mask: Option<&PyFrameMask>) -> PyResult<f64> {
+        if let Some(m) = mask {
+            m.check(self).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError,_>(e.to_string()))?;
+            return Ok(self.calculate_difference_masked(other, m, block_size.unwrap_or(8192), true));
+        }
         Ok(self.calculate_difference_parallel_simd(other, block_size.unwrap_or(8192), true))
     }
+    /// 裁剪出指定区域，返回新帧
+    fn crop(&self, rect: &PyCropRect) -> PyVideoFrame { crop_frame(self, rect) }
+    /// 拉普拉斯方差（清晰度）
+    fn sharpness(&self) -> f64 { quality::laplacian_variance(self.pixels(), self.width, self.height) }
+    fn exposure(&self) -> f64 { quality::compute_quality(self).exposure }
+    fn contrast(&self) -> f64 { quality::compute_quality(self).contrast }
+    fn entropy(&self) -> f64 { let p = self.pixels(); quality::histogram_entropy(&quality::histogram(p), p.len()) }
+    fn quality_score(&self) -> f64 { quality::compute_quality(self).score() }
+    fn quality_metrics(&self) -> HashMap<String, f64> { let q = quality::compute_quality(self); let mut m = HashMap::new();
+        m.insert("sharpness".into(), q.sharpness); m.insert("exposure".into(), q.exposure); m.insert("contrast".into(), q.contrast);
+        m.insert("entropy".into(), q.entropy); m.insert("score".into(), q.score()); m }
 }
 
 impl PyVideoFrame {
+    pub fn from_data(frame_number: usize, width: usize, height: usize, data: FrameData, timestamp: f64) -> Self { Self { frame_number, width, height, timestamp, data } }
+    /// 全零的独占缓冲帧，供来源逐帧复用
+    pub fn blank(width: usize, height: usize) -> Self { Self::from_data(0, width, height, FrameData::Owned(vec![0; width * height]), 0.0) }
+    /// 有效像素（Python 传入的数据可能长于 width*height）
+    pub fn pixels(&self) -> &[u8] { &self.data[..self.width * self.height] }
+    /// 可写的有效像素；共享数据先转为独占缓冲。来源据此把每帧读入同一个复用的帧
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        let n = self.width * self.height;
+        if let FrameData::Shared { .. } = self.data { self.data = FrameData::Owned(self.pixels().to_vec()); }
+        match &mut self.data { FrameData::Owned(v) => { v.resize(n, 0); v }, FrameData::Shared { .. } => unreachable!("converted to owned above") }
+    }
+    /// 复制另一帧的内容，复用本帧已有的独占缓冲；共享数据只增加引用
+    pub fn copy_from(&mut self, other: &PyVideoFrame) {
+        match (&mut self.data, &other.data) {
+            (FrameData::Owned(v), FrameData::Owned(_)) => { v.clear(); v.extend_from_slice(other.pixels()); }
+            (data, _) => *data = other.data.clone(),
+        }
+        (self.frame_number, self.width, self.height, self.timestamp) = (other.frame_number, other.width, other.height, other.timestamp);
+    }
     pub fn calculate_difference_parallel_simd(&self, other: &PyVideoFrame, block_size: usize, use_simd: bool) -> f64 {
         use rayon::prelude::*;
         if self.width != other.width || self.height != other.height { return f64::MAX; }
@@ -55,6 +116,23 @@ impl PyVideoFrame {
         }).sum();
         total_diff as f64 / total_pixels as f64
     }
+    /// 掩码版平均像素差：只统计掩码非零的像素，结果按有效像素数归一化
+    pub fn calculate_difference_masked(&self, other: &PyVideoFrame, mask: &PyFrameMask, block_size: usize, use_simd: bool) -> f64 {
+        use rayon::prelude::*;
+        if self.width != other.width || self.height != other.height || !mask.matches(self) { return f64::MAX; }
+        if mask.active_pixels() == 0 { return 0.0; }
+        let total_pixels = self.width * self.height; let block_size = block_size.clamp(1, total_pixels.max(1));
+        let num_blocks = total_pixels.div_ceil(block_size);
+        let total_diff: u64 = (0..num_blocks).into_par_iter().map(|i| {
+            let start = i * block_size; let end = ((i+1)*block_size).min(total_pixels);
+            if use_simd { #[cfg(target_arch="x86_64")] unsafe {
+                if std::arch::is_x86_feature_detected!("avx2") { return masked_sad_avx2(&self.data[start..end], &other.data[start..end], &mask.data[start..end]); }
+                if std::arch::is_x86_feature_detected!("sse2") { return masked_sad_sse2(&self.data[start..end], &other.data[start..end], &mask.data[start..end]); }
+            }}
+            masked_sad_scalar(&self.data[start..end], &other.data[start..end], &mask.data[start..end])
+        }).sum();
+        total_diff as f64 / mask.active_pixels() as f64
+    }
     #[cfg(target_arch="x86_64")]
     #[target_feature(enable="avx2")]
     unsafe fn calculate_difference_avx2_block(&self, other: &[u8], start: usize, len: usize) -> u64 {
@@ -79,4 +157,39 @@ impl PyVideoFrame {
         for i in (start + chunks*16)..(start+len) { total += (self.data[i] as i32 - other[i] as i32).abs() as u64; }
         total
     }
-}
\ No newline at end of file
+}
+
+fn masked_sad_scalar(a: &[u8], b: &[u8], m: &[u8]) -> u64 {
+    a.iter().zip(b).zip(m).map(|((x, y), k)| (x.abs_diff(*y) & k) as u64).sum()
+}
+
+/// |a-b| = subs(a,b) | subs(b,a)，与掩码按位与后对 0 做 SAD 求和
+#[cfg(target_arch="x86_64")]
+#[target_feature(enable="avx2")]
+unsafe fn masked_sad_avx2(a: &[u8], b: &[u8], m: &[u8]) -> u64 {
+    let chunks = a.len() / 32; let zero = _mm256_setzero_si256(); let mut acc = _mm256_setzero_si256();
+    for i in 0..chunks { let off = i * 32;
+        let va = _mm256_loadu_si256(a.as_ptr().add(off) as *const __m256i);
+        let vb = _mm256_loadu_si256(b.as_ptr().add(off) as *const __m256i);
+        let vm = _mm256_loadu_si256(m.as_ptr().add(off) as *const __m256i);
+        let diff = _mm256_and_si256(_mm256_or_si256(_mm256_subs_epu8(va, vb), _mm256_subs_epu8(vb, va)), vm);
+        acc = _mm256_add_epi64(acc, _mm256_sad_epu8(diff, zero));
+    }
+    let total = _mm256_extract_epi64(acc,0) as u64 + _mm256_extract_epi64(acc,1) as u64 + _mm256_extract_epi64(acc,2) as u64 + _mm256_extract_epi64(acc,3) as u64;
+    total + masked_sad_scalar(&a[chunks*32..], &b[chunks*32..], &m[chunks*32..])
+}
+
+#[cfg(target_arch="x86_64")]
+#[target_feature(enable="sse2")]
+unsafe fn masked_sad_sse2(a: &[u8], b: &[u8], m: &[u8]) -> u64 {
+    let chunks = a.len() / 16; let zero = _mm_setzero_si128(); let mut acc = _mm_setzero_si128();
+    for i in 0..chunks { let off = i * 16;
+        let va = _mm_loadu_si128(a.as_ptr().add(off) as *const __m128i);
+        let vb = _mm_loadu_si128(b.as_ptr().add(off) as *const __m128i);
+        let vm = _mm_loadu_si128(m.as_ptr().add(off) as *const __m128i);
+        let diff = _mm_and_si128(_mm_or_si128(_mm_subs_epu8(va, vb), _mm_subs_epu8(vb, va)), vm);
+        acc = _mm_add_epi64(acc, _mm_sad_epu8(diff, zero));
+    }
+    let total = _mm_cvtsi128_si64(acc) as u64 + _mm_cvtsi128_si64(_mm_unpackhi_epi64(acc, acc)) as u64;
+    total + masked_sad_scalar(&a[chunks*16..], &b[chunks*16..], &m[chunks*16..])
+}
@@ -0,0 +1,64 @@
+use pyo3::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::mask::PyFrameMask;
+
+/// 画面静止段（帧区间为闭区间，时间单位秒）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyFrozenSegment {
+    #[pyo3(get)] pub start_frame: usize,
+    #[pyo3(get)] pub end_frame: usize,
+    #[pyo3(get)] pub start_time: f64,
+    #[pyo3(get)] pub end_time: f64,
+    #[pyo3(get)] pub duration: f64,
+}
+
+#[pymethods]
+impl PyFrozenSegment {
+    fn __repr__(&self) -> String { format!("PyFrozenSegment(start_frame={}, end_frame={}, duration={:.3})", self.start_frame, self.end_frame, self.duration) }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrozenParams {
+    /// 与静止段首帧的平均像素差 <= 该值视为未变化
+    pub noise_tolerance: f64,
+    /// 最短持续时间（秒）
+    pub min_duration: f64,
+}
+
+impl Default for FrozenParams { fn default() -> Self { Self { noise_tolerance: 1.0, min_duration: 2.0 } } }
+
+/// 与 ffmpeg freezedetect 相同，每一帧都与当前静止段的首帧比较，避免缓慢漂移被逐帧差掩盖
+pub fn detect_frozen_segments(frames: &[PyVideoFrame], params: &FrozenParams, use_simd: bool, block_size: usize, mask: Option<&PyFrameMask>) -> Vec<PyFrozenSegment> {
+    let mut segments = Vec::new();
+    if frames.len() < 2 { return segments; }
+    let diff = |a: &PyVideoFrame, b: &PyVideoFrame| match mask {
+        Some(m) => a.calculate_difference_masked(b, m, block_size, use_simd),
+        None if use_simd => a.calculate_difference_parallel_simd(b, block_size, true),
+        None => a.calculate_difference(b, None).unwrap_or(f64::MAX),
+    };
+    let mut push = |start: usize, end: usize| {
+        let (start_time, end_time) = (frames[start].timestamp, frames[end].timestamp);
+        if end > start && end_time - start_time >= params.min_duration {
+            segments.push(PyFrozenSegment { start_frame: start, end_frame: end, start_time, end_time, duration: end_time - start_time });
+        }
+    };
+    let mut anchor = 0;
+    for i in 1..frames.len() {
+        if diff(&frames[anchor], &frames[i]) > params.noise_tolerance { push(anchor, i - 1); anchor = i; }
+    }
+    push(anchor, frames.len() - 1);
+    segments
+}
+
+/// 同一静止段内的多个关键帧只保留第一个
+pub fn collapse_frozen(keyframes: &[usize], segments: &[PyFrozenSegment]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(keyframes.len()); let mut last_segment: Option<usize> = None;
+    for &k in keyframes {
+        let seg = segments.iter().position(|s| k >= s.start_frame && k <= s.end_frame);
+        if seg.is_some() && seg == last_segment { continue; }
+        last_segment = seg; out.push(k);
+    }
+    out
+}
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+pub trait ReadSeek: BufRead + Seek {}
+impl<T: BufRead + Seek> ReadSeek for T {}
+
+/// 视频输入：文件路径（可为非 UTF-8）或已在内存中的完整文件内容
+#[derive(Debug, Clone)]
+pub enum VideoInput {
+    Path(PathBuf),
+    Bytes(Arc<[u8]>),
+}
+
+impl From<PathBuf> for VideoInput { fn from(p: PathBuf) -> Self { Self::Path(p) } }
+impl From<Vec<u8>> for VideoInput { fn from(d: Vec<u8>) -> Self { Self::Bytes(d.into()) } }
+
+impl VideoInput {
+    pub fn reader(&self) -> Result<Box<dyn ReadSeek>> {
+        Ok(match self {
+            Self::Path(p) => Box::new(BufReader::new(File::open(p).with_context(|| format!("Failed to open {}", p.display()))?)),
+            Self::Bytes(d) => Box::new(Cursor::new(d.clone())),
+        })
+    }
+    /// 文件头前 n 个字节（不足时返回实际长度）
+    pub fn head(&self, n: usize) -> Vec<u8> {
+        match self {
+            Self::Path(p) => { let mut buf = Vec::with_capacity(n); if let Ok(f) = File::open(p) { let _ = f.take(n as u64).read_to_end(&mut buf); } buf }
+            Self::Bytes(d) => d[..n.min(d.len())].to_vec(),
+        }
+    }
+    pub fn is_dir(&self) -> bool { matches!(self, Self::Path(p) if p.is_dir()) }
+    /// 日志与结果中使用的名称
+    pub fn display_name(&self) -> String {
+        match self {
+            Self::Path(p) => p.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| p.display().to_string()),
+            Self::Bytes(d) => format!("<memory: {} bytes>", d.len()),
+        }
+    }
+    /// 输出文件命名用的主干名（不含扩展名），内存输入为 "memory"
+    pub fn stem(&self) -> String {
+        match self { Self::Path(p) => p.file_stem().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "video".into()), Self::Bytes(_) => "memory".into() }
+    }
+    /// 为 ffmpeg 添加输入参数：路径以 OsStr 直接传递，内存数据走 stdin（pipe:0）
+    pub fn add_ffmpeg_input(&self, cmd: &mut Command) {
+        match self { Self::Path(p) => { cmd.arg("-i").arg(p); } Self::Bytes(_) => { cmd.args(["-i", "pipe:0"]).stdin(Stdio::piped()); } }
+    }
+    /// 子进程启动后由后台线程写入 stdin，避免与读取 stdout 互相阻塞；ffmpeg 提前退出导致的 BrokenPipe 忽略
+    pub fn feed(&self, child: &mut Child) -> Option<JoinHandle<()>> {
+        let Self::Bytes(d) = self else { return None };
+        let (mut stdin, d) = (child.stdin.take()?, d.clone());
+        Some(std::thread::spawn(move || { let _ = stdin.write_all(&d); }))
+    }
+    /// 运行 ffmpeg 并收集全部输出（探测等短命令）
+    pub fn ffmpeg_output(&self, cmd: &mut Command) -> Result<Output> {
+        let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().context("Failed to spawn FFmpeg process")?;
+        let writer = self.feed(&mut child);
+        let output = child.wait_with_output().context("Failed to wait for FFmpeg")?;
+        if let Some(w) = writer { let _ = w.join(); }
+        Ok(output)
+    }
+    /// 需要文件路径时使用：内存数据写入临时文件（如 moov 在末尾的 MP4 需要随机访问），临时文件随第二个返回值释放
+    pub fn as_file(&self) -> Result<(PathBuf, Option<tempfile::NamedTempFile>)> {
+        match self {
+            Self::Path(p) => Ok((p.clone(), None)),
+            Self::Bytes(d) => {
+                let mut tmp = tempfile::Builder::new().prefix("inkfox-").tempfile().context("Failed to create temp file")?;
+                tmp.write_all(d).context("Failed to write temp file")?; tmp.flush()?;
+                Ok((tmp.path().to_path_buf(), Some(tmp)))
+            }
+        }
+    }
+}
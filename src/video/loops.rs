@@ -0,0 +1,71 @@
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::video::diversity::{downsample, phash};
+use crate::video::frame::PyVideoFrame;
+
+/// 循环动画检测结果：从 start_frame 起每 period 帧重复一次
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyLoopInfo {
+    #[pyo3(get)] pub start_frame: usize,
+    #[pyo3(get)] pub period: usize,
+    /// 片段内重复的周期数（可为小数）
+    #[pyo3(get)] pub cycles: f64,
+    /// 对齐帧之间的平均缩略图差异（0..=255，越小越可信）
+    #[pyo3(get)] pub mean_diff: f64,
+}
+
+#[pymethods]
+impl PyLoopInfo {
+    /// 第一个完整周期的结束帧（开区间）
+    #[getter]
+    pub fn end_frame(&self) -> usize { self.start_frame + self.period }
+    fn __repr__(&self) -> String { format!("PyLoopInfo(start_frame={}, period={}, cycles={:.2}, mean_diff={:.3})", self.start_frame, self.period, self.cycles, self.mean_diff) }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopParams {
+    /// 32x32 缩略图平均差 <= 该值视为同一画面
+    pub max_diff: f64,
+    /// pHash 汉明距离超过该值直接判为不同（快速预筛）
+    pub max_hash_distance: u32,
+    pub min_period: usize,
+    /// 0 表示不限（至多帧数 / min_cycles）
+    pub max_period: usize,
+    /// 至少重复这么多个周期才认为是循环
+    pub min_cycles: f64,
+}
+
+impl Default for LoopParams {
+    fn default() -> Self { Self { max_diff: 3.0, max_hash_distance: 10, min_period: 2, max_period: 0, min_cycles: 2.0 } }
+}
+
+const THUMB: usize = 32;
+
+fn thumb_diff(a: &[f32], b: &[f32]) -> f64 { a.iter().zip(b).map(|(x, y)| (x - y).abs() as f64).sum::<f64>() / a.len().max(1) as f64 }
+
+/// 取满足条件的最小周期（其整数倍同样满足）；start 为从该处起到结尾都按周期重复的最早帧。
+/// 周期内必须有画面变化，纯静止片段不算循环。
+pub fn detect_loop(frames: &[PyVideoFrame], params: &LoopParams) -> Option<PyLoopInfo> {
+    let n = frames.len(); let min_cycles = params.min_cycles.max(1.0);
+    let max_period = ((n as f64 / min_cycles) as usize).min(if params.max_period == 0 { usize::MAX } else { params.max_period });
+    if n < 2 || max_period < params.min_period.max(1) { return None; }
+    let (hashes, thumbs): (Vec<u64>, Vec<Vec<f32>>) = frames.par_iter().map(|f| (phash(f), downsample(f, THUMB))).unzip();
+    let same = |i: usize, j: usize| -> Option<f64> {
+        if (hashes[i] ^ hashes[j]).count_ones() > params.max_hash_distance { return None; }
+        let d = thumb_diff(&thumbs[i], &thumbs[j]); (d <= params.max_diff).then_some(d)
+    };
+    let motion: Vec<f64> = thumbs.windows(2).map(|w| thumb_diff(&w[0], &w[1])).collect();
+    for period in params.min_period.max(1)..=max_period {
+        // 从结尾向前扩展对齐区间
+        let (mut start, mut total) = (n - period, 0.0);
+        while start > 0 { match same(start - 1, start - 1 + period) { Some(d) => { total += d; start -= 1; } None => break } }
+        let pairs = n - period - start; if pairs == 0 { continue; }
+        let cycles = (n - start) as f64 / period as f64;
+        if cycles < min_cycles { continue; }
+        if !motion[start..start + period - 1].iter().any(|&d| d > params.max_diff) { continue; }
+        return Some(PyLoopInfo { start_frame: start, period, cycles, mean_diff: total / pairs as f64 });
+    }
+    None
+}
@@ -0,0 +1,62 @@
+use pyo3::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+
+/// 感兴趣区域掩码：0xFF 参与计算，0x00 跳过（台标、字幕、弹幕、计时器等）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyFrameMask {
+    #[pyo3(get)] pub width: usize,
+    #[pyo3(get)] pub height: usize,
+    pub data: Vec<u8>,
+    active: usize,
+}
+
+/// (x, y, w, h)
+pub type Rect = (usize, usize, usize, usize);
+
+#[pymethods]
+impl PyFrameMask {
+    /// include 为空表示整帧参与；exclude 在 include 之后应用
+    #[new]
+    #[pyo3(signature = (width, height, include=None, exclude=None))]
+    pub fn new(width: usize, height: usize, include: Option<Vec<Rect>>, exclude: Option<Vec<Rect>>) -> Self {
+        let include = include.unwrap_or_default();
+        let mut data = vec![if include.is_empty() { 0xFF } else { 0x00 }; width * height];
+        let mut fill = |r: &Rect, v: u8| {
+            let (x0, y0) = (r.0.min(width), r.1.min(height)); let (x1, y1) = ((r.0 + r.2).min(width), (r.1 + r.3).min(height));
+            for y in y0..y1 { data[y * width + x0..y * width + x1].fill(v); }
+        };
+        for r in &include { fill(r, 0xFF); }
+        for r in exclude.unwrap_or_default().iter() { fill(r, 0x00); }
+        Self::from_data(width, height, data)
+    }
+    /// 由掩码帧构造：像素 >= threshold 的位置参与计算
+    #[staticmethod]
+    #[pyo3(signature = (frame, threshold=128))]
+    pub fn from_frame(frame: &PyVideoFrame, threshold: u8) -> Self {
+        let data = frame.pixels().iter().map(|&p| if p >= threshold { 0xFF } else { 0x00 }).collect();
+        Self::from_data(frame.width, frame.height, data)
+    }
+    #[getter]
+    pub fn active_pixels(&self) -> usize { self.active }
+    /// 参与计算的像素占比
+    #[getter]
+    fn coverage(&self) -> f64 { if self.data.is_empty() { 0.0 } else { self.active as f64 / self.data.len() as f64 } }
+    fn __repr__(&self) -> String { format!("PyFrameMask({}x{}, coverage={:.3})", self.width, self.height, self.coverage()) }
+}
+
+impl PyFrameMask {
+    pub fn from_data(width: usize, height: usize, data: Vec<u8>) -> Self {
+        let active = data.iter().filter(|&&m| m != 0).count();
+        Self { width, height, data, active }
+    }
+    /// 两个掩码的交集
+    pub fn intersect(&self, other: &PyFrameMask) -> PyFrameMask {
+        Self::from_data(self.width, self.height, self.data.iter().zip(&other.data).map(|(a, b)| a & b).collect())
+    }
+    pub fn matches(&self, frame: &PyVideoFrame) -> bool { self.width == frame.width && self.height == frame.height }
+    pub fn check(&self, frame: &PyVideoFrame) -> anyhow::Result<()> {
+        if self.matches(frame) { Ok(()) } else { Err(anyhow::anyhow!("Mask is {}x{} but frames are {}x{}", self.width, self.height, frame.width, frame.height)) }
+    }
+}
@@ -1,8 +1,41 @@
+pub mod animation;
+pub mod batch;
+pub mod chunked;
+pub mod crop;
+pub mod curve;
+pub mod diversity;
+pub mod encode;
 pub mod extractor;
 pub mod frame;
+pub mod frozen;
+pub mod input;
+pub mod loops;
+pub mod mask;
+pub mod naming;
 pub mod performance;
+pub mod preview;
+pub mod quality;
+pub mod slides;
+pub mod source;
+pub mod sprites;
+pub mod stats;
+pub mod store;
+pub mod storyboard;
+pub mod transition;
 pub mod utils;
 
+pub use batch::{PyBatchItem, PyBatchResult};
+pub use crop::PyCropRect;
+pub use curve::PyDifferenceCurve;
+pub use encode::PyEncodedKeyframe;
 pub use extractor::VideoKeyframeExtractor;
 pub use frame::PyVideoFrame;
-pub use performance::PyPerformanceResult;
\ No newline at end of file
+pub use frozen::PyFrozenSegment;
+pub use loops::PyLoopInfo;
+pub use source::PyFrameSource;
+pub use sprites::PySpriteSheet;
+pub use mask::PyFrameMask;
+pub use performance::PyPerformanceResult;
+pub use stats::PyFrameStats;
+pub use storyboard::PyStoryboard;
+pub use transition::PyTransitionSegment;
\ No newline at end of file
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::video::encode::ImageFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field { VideoStem, Index, Frame, PtsMs, Score }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// 整数字段为补零宽度，score 为小数位数
+    Field(Field, Option<usize>),
+}
+
+/// 输出文件名模板（不含扩展名），占位符：
+/// `{video_stem}` `{index}`（从 1 开始的序号）`{frame}` `{pts_ms}` `{score}`；
+/// 整数可写 `{frame:06}` 指定补零宽度，`{score:.3}` 指定小数位数。
+/// `{index}` 未指定宽度时按总数自动补零（至少 3 位）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate { segments: Vec<Segment> }
+
+impl NameTemplate {
+    pub const DEFAULT: &'static str = "keyframe_{index}";
+
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let (mut segments, mut rest) = (Vec::new(), template);
+        let literal = |s: &str| if s.contains('}') { Err(format!("Unmatched '}}' in template '{}'", template)) } else { Ok(Segment::Literal(s.to_string())) };
+        while let Some(open) = rest.find('{') {
+            if open > 0 { segments.push(literal(&rest[..open])?); }
+            let close = rest[open..].find('}').ok_or_else(|| format!("Unclosed '{{' in template '{}'", template))? + open;
+            let (name, spec) = match rest[open + 1..close].split_once(':') { Some((n, s)) => (n, Some(s)), None => (&rest[open + 1..close], None) };
+            let field = match name {
+                "video_stem" => Field::VideoStem, "index" => Field::Index, "frame" => Field::Frame, "pts_ms" => Field::PtsMs, "score" => Field::Score,
+                _ => return Err(format!("Unknown placeholder '{{{}}}', expected video_stem/index/frame/pts_ms/score", name)),
+            };
+            let width = match (field, spec) {
+                (_, None) => None,
+                (Field::VideoStem, Some(_)) => return Err("{video_stem} takes no format spec".into()),
+                (Field::Score, Some(s)) => Some(s.strip_prefix('.').and_then(|p| p.parse().ok()).ok_or_else(|| format!("Bad score spec '{}', expected e.g. .3", s))?),
+                (_, Some(s)) => Some(s.parse().map_err(|_| format!("Bad width spec '{}', expected e.g. 03", s))?),
+            };
+            segments.push(Segment::Field(field, width));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() { segments.push(literal(rest)?); }
+        if segments.is_empty() { return Err("Empty filename template".into()); }
+        Ok(Self { segments })
+    }
+
+    pub fn uses_score(&self) -> bool { self.segments.iter().any(|s| matches!(s, Segment::Field(Field::Score, _))) }
+
+    pub fn render(&self, ctx: &NameContext) -> String {
+        self.segments.iter().map(|s| match *s {
+            Segment::Literal(ref l) => l.clone(),
+            Segment::Field(Field::VideoStem, _) => ctx.video_stem.to_string(),
+            Segment::Field(Field::Index, w) => format!("{:0w$}", ctx.index, w = w.unwrap_or_else(|| ctx.total.to_string().len().max(3))),
+            Segment::Field(Field::Frame, w) => format!("{:0w$}", ctx.frame, w = w.unwrap_or(0)),
+            Segment::Field(Field::PtsMs, w) => format!("{:0w$}", (ctx.timestamp * 1000.0).round() as u64, w = w.unwrap_or(0)),
+            Segment::Field(Field::Score, p) => match ctx.score { Some(v) => format!("{:.p$}", v, p = p.unwrap_or(2)), None => "nan".into() },
+        }).collect()
+    }
+}
+
+impl Default for NameTemplate {
+    fn default() -> Self { Self::parse(Self::DEFAULT).expect("default template is valid") }
+}
+
+/// 渲染单个文件名所需的信息
+#[derive(Debug, Clone)]
+pub struct NameContext<'a> {
+    pub video_stem: &'a str,
+    /// 从 1 开始的保存序号
+    pub index: usize,
+    pub total: usize,
+    pub frame: usize,
+    pub timestamp: f64,
+    pub score: Option<f64>,
+}
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionPolicy {
+    #[default]
+    Overwrite,
+    /// 保留已有文件，不再写出该帧
+    Skip,
+    /// 追加 _1、_2 ... 直到文件名可用
+    Unique,
+}
+
+impl CollisionPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() { "overwrite" => Some(Self::Overwrite), "skip" => Some(Self::Skip), "unique" => Some(Self::Unique), _ => None }
+    }
+}
+
+/// 保存关键帧时的命名与编码选项；默认值与原有 keyframe_001.jpg 行为一致
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    pub template: NameTemplate,
+    pub format: ImageFormat,
+    /// 1..=100；JPEG 95 及以上等同于 ffmpeg -q:v 2，PNG 忽略
+    pub quality: u8,
+    /// 长边上限（像素），0 表示保持原尺寸
+    pub max_dimension: u32,
+    pub collision: CollisionPolicy,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self { Self { template: NameTemplate::default(), format: ImageFormat::Jpeg, quality: 95, max_dimension: 0, collision: CollisionPolicy::default() } }
+}
+
+impl SaveOptions {
+    /// 计算输出路径；Skip 策略下目标已存在时返回 None。`taken` 记录本次已分配的路径，避免同批次内互相覆盖
+    pub fn target(&self, out_dir: &Path, ctx: &NameContext, taken: &mut HashSet<PathBuf>) -> Option<PathBuf> {
+        let (stem, ext) = (self.template.render(ctx), self.format.extension());
+        let path = out_dir.join(format!("{}.{}", stem, ext));
+        let path = match self.collision {
+            CollisionPolicy::Overwrite => path,
+            CollisionPolicy::Skip => if path.exists() || taken.contains(&path) { return None } else { path },
+            CollisionPolicy::Unique => (0..).map(|n| if n == 0 { path.clone() } else { out_dir.join(format!("{}_{}.{}", stem, n, ext)) })
+                .find(|p| !p.exists() && !taken.contains(p)).expect("unbounded search"),
+        };
+        taken.insert(path.clone());
+        Some(path)
+    }
+
+    /// ffmpeg 输出端的编码参数
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let q = self.quality.clamp(1, 100) as u32;
+        match self.format {
+            // 质量 95..=100 -> -q:v 2，1 -> 31
+            ImageFormat::Jpeg => vec!["-q:v".into(), (2 + (95 - q.min(95)) * 29 / 94).to_string()],
+            ImageFormat::Png => vec!["-c:v".into(), "png".into()],
+            ImageFormat::WebP => vec!["-c:v".into(), "libwebp".into(), "-quality".into(), q.to_string()],
+        }
+    }
+
+    /// 追加到 ffmpeg 滤镜链末尾的缩放（只缩小不放大）
+    pub fn scale_filter(&self) -> Option<String> {
+        (self.max_dimension > 0).then(|| format!("scale='min({d},iw)':'min({d},ih)':force_original_aspect_ratio=decrease", d = self.max_dimension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(index: usize, total: usize) -> NameContext<'static> {
+        NameContext { video_stem: "clip", index, total, frame: 42, timestamp: 1.2345, score: Some(0.123456) }
+    }
+
+    fn render(template: &str, ctx: &NameContext) -> String { NameTemplate::parse(template).unwrap().render(ctx) }
+
+    #[test]
+    fn default_index_width_follows_total() {
+        assert_eq!(render(NameTemplate::DEFAULT, &ctx(7, 12)), "keyframe_007");
+        assert_eq!(render(NameTemplate::DEFAULT, &ctx(7, 12345)), "keyframe_00007");
+    }
+
+    #[test]
+    fn explicit_widths_pad_integers() {
+        let c = ctx(3, 10);
+        assert_eq!(render("{video_stem}_{index:02}_{frame:06}_{pts_ms:08}", &c), "clip_03_000042_00001235");
+        assert_eq!(render("{frame}-{pts_ms}", &c), "42-1235");
+        // 宽度不足时不截断
+        assert_eq!(render("{frame:1}", &c), "42");
+    }
+
+    #[test]
+    fn score_precision() {
+        let c = ctx(1, 1);
+        assert_eq!(render("{score}", &c), "0.12");
+        assert_eq!(render("{score:.4}", &c), "0.1235");
+        assert_eq!(render("{score:.0}", &c), "0");
+        assert_eq!(render("{score}", &NameContext { score: None, ..c }), "nan");
+        assert!(NameTemplate::parse("{score}").unwrap().uses_score());
+        assert!(!NameTemplate::parse("{index}").unwrap().uses_score());
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        for bad in ["{nope}", "{index", "index}", "a}{index}", "{in{dex}", "", "{video_stem:03}", "{score:3}", "{frame:x}"] {
+            assert!(NameTemplate::parse(bad).is_err(), "{:?} should be rejected", bad);
+        }
+        assert!(NameTemplate::parse("{nope}").unwrap_err().contains("Unknown placeholder '{nope}'"));
+        assert!(NameTemplate::parse("{index").unwrap_err().contains("Unclosed"));
+        assert!(NameTemplate::parse("index}").unwrap_err().contains("Unmatched"));
+    }
+
+    fn options(collision: CollisionPolicy) -> SaveOptions { SaveOptions { template: NameTemplate::parse("kf").unwrap(), collision, ..SaveOptions::default() } }
+
+    #[test]
+    fn collision_policies() {
+        let dir = tempfile::tempdir().unwrap(); let existing = dir.path().join("kf.jpg");
+        std::fs::write(&existing, b"").unwrap();
+        let c = ctx(1, 1);
+
+        let mut taken = HashSet::new();
+        assert_eq!(options(CollisionPolicy::Overwrite).target(dir.path(), &c, &mut taken), Some(existing.clone()));
+
+        let mut taken = HashSet::new();
+        assert_eq!(options(CollisionPolicy::Skip).target(dir.path(), &c, &mut taken), None);
+
+        let mut taken = HashSet::new(); let unique = options(CollisionPolicy::Unique);
+        assert_eq!(unique.target(dir.path(), &c, &mut taken), Some(dir.path().join("kf_1.jpg")));
+        // 同批次已分配的路径也视为占用
+        assert_eq!(unique.target(dir.path(), &c, &mut taken), Some(dir.path().join("kf_2.jpg")));
+    }
+
+    #[test]
+    fn skip_honours_paths_taken_in_same_batch() {
+        let dir = tempfile::tempdir().unwrap(); let (skip, c) = (options(CollisionPolicy::Skip), ctx(1, 1));
+        let mut taken = HashSet::new();
+        assert_eq!(skip.target(dir.path(), &c, &mut taken), Some(dir.path().join("kf.jpg")));
+        assert_eq!(skip.target(dir.path(), &c, &mut taken), None);
+    }
+}
@@ -1,3 +1,6 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
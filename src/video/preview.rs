@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::video::crop::PyCropRect;
+use crate::video::input::VideoInput;
+use crate::video::utils::probe_seekable;
+
+/// 动态预览的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat { Gif, WebP, Mp4 }
+
+impl PreviewFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() { "gif" => Some(Self::Gif), "webp" => Some(Self::WebP), "mp4" => Some(Self::Mp4), _ => None }
+    }
+    /// 按输出文件扩展名推断
+    pub fn from_path(path: &Path) -> Option<Self> { path.extension().and_then(|e| e.to_str()).and_then(Self::parse) }
+}
+
+#[derive(Debug, Clone)]
+pub struct PreviewParams {
+    pub format: PreviewFormat,
+    /// 每个关键帧前后共截取的秒数；0 表示只拼接关键帧本身（幻灯片式）
+    pub window: f64,
+    /// 幻灯片模式下每个关键帧的停留秒数
+    pub hold: f64,
+    pub fps: f64,
+    /// 输出宽度（高度按比例取偶数），0 表示保持原尺寸
+    pub width: u32,
+    /// GIF/WebP 循环：0 为无限循环，-1 为只播放一次，n 为额外重复 n 次；MP4 忽略
+    pub loop_count: i32,
+    /// WebP/MP4 的质量 1..=100（GIF 使用调色板，忽略）
+    pub quality: u8,
+}
+
+impl Default for PreviewParams {
+    fn default() -> Self { Self { format: PreviewFormat::Gif, window: 0.0, hold: 0.5, fps: 10.0, width: 480, loop_count: 0, quality: 75 } }
+}
+
+/// 关键帧附近的时间窗口，重叠或相接的窗口合并
+fn merge_windows(indices: &[usize], fps: f64, window: f64) -> Vec<(f64, f64)> {
+    let mut ranges: Vec<(f64, f64)> = indices.iter().map(|&i| { let t = i as f64 / fps; ((t - window / 2.0).max(0.0), t + window / 2.0) }).collect();
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(ranges.len());
+    for (s, e) in ranges { match merged.last_mut() { Some(last) if s <= last.1 => last.1 = last.1.max(e), _ => merged.push((s, e)) } }
+    merged
+}
+
+/// 用一次 ffmpeg 调用把关键帧（或其附近片段）导出为单个 GIF/WebP/MP4，返回预览时长（秒）
+pub fn export_preview(input: &VideoInput, ffmpeg_path: &Path, indices: &[usize], output: &Path, crop: Option<&PyCropRect>, params: &PreviewParams) -> Result<f64> {
+    let mut wanted = indices.to_vec(); wanted.sort_unstable(); wanted.dedup();
+    if wanted.is_empty() { anyhow::bail!("No keyframes to export"); }
+    let (info, input, _spilled) = probe_seekable(input, ffmpeg_path)?;
+    let (mut filters, duration) = if params.window > 0.0 {
+        // 片段模式：选出窗口内的帧后重排时间戳，使片段首尾相接
+        let windows = merge_windows(&wanted, info.fps, params.window);
+        let expr = windows.iter().map(|(s, e)| format!("between(t,{:.3},{:.3})", s, e)).collect::<Vec<_>>().join("+");
+        (vec![format!("select='{}'", expr), "setpts=N/FRAME_RATE/TB".to_string()], windows.iter().map(|(s, e)| e - s).sum::<f64>())
+    } else {
+        // 幻灯片模式：每个关键帧占 hold 秒，末帧补足停留时间
+        let expr = wanted.iter().map(|i| format!("eq(n,{})", i)).collect::<Vec<_>>().join("+");
+        (vec![format!("select='{}'", expr), format!("setpts=N*{}/TB", params.hold), format!("tpad=stop_mode=clone:stop_duration={}", params.hold)], wanted.len() as f64 * params.hold)
+    };
+    if let Some(c) = crop { filters.insert(1, c.to_ffmpeg_filter()); }
+    filters.push(format!("fps={}", params.fps));
+    if params.width > 0 { filters.push(format!("scale={}:-2:flags=lanczos", params.width)); }
+    else if params.format == PreviewFormat::Mp4 { filters.push("scale=trunc(iw/2)*2:trunc(ih/2)*2".into()); }
+    let mut chain = filters.join(",");
+    if params.format == PreviewFormat::Gif { chain.push_str(",split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer"); }
+
+    if let Some(dir) = output.parent().filter(|d| !d.as_os_str().is_empty()) { std::fs::create_dir_all(dir).context("Failed to create output directory")?; }
+    let mut cmd = Command::new(ffmpeg_path); cmd.args(["-y", "-hide_banner", "-loglevel", "error"]); input.add_ffmpeg_input(&mut cmd);
+    cmd.args(["-an", "-filter_complex", &chain]);
+    let quality = params.quality.clamp(1, 100);
+    match params.format {
+        PreviewFormat::Gif => { cmd.args(["-loop", &params.loop_count.to_string()]); }
+        PreviewFormat::WebP => { cmd.args(["-c:v", "libwebp", "-quality", &quality.to_string(), "-loop", &(if params.loop_count < 0 { 1 } else { params.loop_count }).to_string()]); }
+        // 质量 1..=100 线性映射到 CRF 51..=18
+        PreviewFormat::Mp4 => { cmd.args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-crf", &(51 - (quality as u32 - 1) * 33 / 99).to_string(), "-movflags", "+faststart"]); }
+    }
+    cmd.arg(output);
+    let out = input.ffmpeg_output(&mut cmd).context("Failed to run FFmpeg for preview")?;
+    if !out.status.success() { anyhow::bail!("FFmpeg preview export failed: {}", String::from_utf8_lossy(&out.stderr).trim()); }
+    Ok(duration)
+}
@@ -0,0 +1,80 @@
+use rayon::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+
+/// 单帧画质指标
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QualityMetrics {
+    /// 拉普拉斯响应方差，越大越清晰
+    pub sharpness: f64,
+    /// 曝光得分 [0,1]：均值接近中灰且过曝/欠曝像素少时接近 1
+    pub exposure: f64,
+    /// RMS 对比度（标准差 / 127.5），[0,1]
+    pub contrast: f64,
+    /// 灰度直方图香农熵（bit），[0,8]
+    pub entropy: f64,
+}
+
+impl QualityMetrics {
+    /// 综合得分 [0,1]，清晰度权重最高（运动模糊/过渡帧最需要排除）
+    pub fn score(&self) -> f64 {
+        let sharp = 1.0 - (-self.sharpness / 500.0).exp();
+        let contrast = (self.contrast * 2.0).min(1.0);
+        0.5 * sharp + 0.2 * self.exposure + 0.15 * contrast + 0.15 * self.entropy / 8.0
+    }
+}
+
+/// 4 邻域拉普拉斯方差
+pub fn laplacian_variance(pixels: &[u8], width: usize, height: usize) -> f64 {
+    if width < 3 || height < 3 { return 0.0; }
+    let (sum, sum_sq) = (1..height - 1).into_par_iter().map(|y| {
+        let (up, row, down) = (&pixels[(y - 1) * width..y * width], &pixels[y * width..(y + 1) * width], &pixels[(y + 1) * width..(y + 2) * width]);
+        let mut s = 0i64; let mut sq = 0i64;
+        for x in 1..width - 1 {
+            let lap = up[x] as i64 + down[x] as i64 + row[x - 1] as i64 + row[x + 1] as i64 - 4 * row[x] as i64;
+            s += lap; sq += lap * lap;
+        }
+        (s, sq)
+    }).reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    let n = ((width - 2) * (height - 2)) as f64; let mean = sum as f64 / n;
+    (sum_sq as f64 / n - mean * mean).max(0.0)
+}
+
+pub fn histogram(pixels: &[u8]) -> [u64; 256] { let mut h = [0u64; 256]; for &p in pixels { h[p as usize] += 1; } h }
+
+pub fn histogram_entropy(hist: &[u64; 256], total: usize) -> f64 {
+    if total == 0 { return 0.0; }
+    let n = total as f64;
+    hist.iter().filter(|c| **c > 0).fold(0.0, |h, &c| { let p = c as f64 / n; h - p * p.log2() })
+}
+
+pub fn compute_quality(frame: &PyVideoFrame) -> QualityMetrics {
+    let pixels = frame.pixels(); let total = pixels.len();
+    if total == 0 { return QualityMetrics::default(); }
+    let hist = histogram(pixels); let n = total as f64;
+    let (mut sum, mut sum_sq) = (0f64, 0f64);
+    for (v, &c) in hist.iter().enumerate() { let c = c as f64; sum += v as f64 * c; sum_sq += (v * v) as f64 * c; }
+    let mean = sum / n; let std = (sum_sq / n - mean * mean).max(0.0).sqrt();
+    let clipped = (hist[..=5].iter().sum::<u64>() + hist[250..].iter().sum::<u64>()) as f64 / n;
+    QualityMetrics {
+        sharpness: laplacian_variance(pixels, frame.width, frame.height),
+        exposure: (1.0 - (mean - 127.5).abs() / 127.5) * (1.0 - clipped),
+        contrast: std / 127.5,
+        entropy: histogram_entropy(&hist, total),
+    }
+}
+
+/// 在每个关键帧之后的 `window` 帧内（不越过下一个关键帧）挑选画质最好的一帧。
+/// `accept` 用于排除不允许选中的帧（如空白帧）。
+pub fn refine_by_quality<F: Fn(usize) -> bool + Sync>(frames: &[PyVideoFrame], keyframes: &[usize], window: usize, accept: F) -> Vec<usize> {
+    if window == 0 { return keyframes.to_vec(); }
+    let mut refined: Vec<usize> = keyframes.par_iter().enumerate().map(|(i, &k)| {
+        let limit = keyframes.get(i + 1).map(|n| n.saturating_sub(1)).unwrap_or(frames.len() - 1);
+        let end = k.saturating_add(window).min(limit).max(k);
+        (k..=end).filter(|&j| j == k || accept(j))
+            .map(|j| (j, compute_quality(&frames[j]).score()))
+            .fold((k, f64::MIN), |best, cur| if cur.1 > best.1 { cur } else { best }).0
+    }).collect();
+    refined.sort_unstable(); refined.dedup();
+    refined
+}
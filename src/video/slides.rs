@@ -0,0 +1,113 @@
+use anyhow::{ensure, Result};
+use rayon::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::mask::PyFrameMask;
+
+/// 幻灯片/录屏模式参数
+#[derive(Debug, Clone)]
+pub struct SlideParams {
+    /// 变化图的网格边长（像素）
+    pub grid: usize,
+    /// 网格内平均像素差超过该值记为脏块
+    pub change_threshold: f64,
+    /// 外接矩形不超过 N x N 个网格的孤立变化视为鼠标指针，忽略
+    pub cursor_size: usize,
+    /// 脏块占比 >= 该值视为换页，否则视为同一页内的增量变化（逐条出现的要点等）
+    pub new_slide_ratio: f64,
+    /// 至少连续静止这么多帧才算一页（过滤切换动画中的中间帧）
+    pub min_stable_frames: usize,
+}
+
+impl Default for SlideParams {
+    fn default() -> Self { Self { grid: 16, change_threshold: 12.0, cursor_size: 3, new_slide_ratio: 0.2, min_stable_frames: 2 } }
+}
+
+/// 一页幻灯片：[start, end] 闭区间，keyframe 为该页最后一个静止帧
+#[derive(Debug, Clone, Copy)]
+pub struct Slide {
+    pub start: usize,
+    pub end: usize,
+    pub keyframe: usize,
+    /// 进入该页时的脏块占比（首页为无穷大），用于超出数量上限时取舍
+    pub strength: f64,
+}
+
+/// 两帧之间的脏块图（按行存储）；尺寸不同的两帧视为整页变化，遮罩或像素缓冲与帧尺寸不符时报错
+pub fn change_map(a: &PyVideoFrame, b: &PyVideoFrame, grid: usize, threshold: f64, mask: Option<&PyFrameMask>) -> Result<(usize, usize, Vec<bool>)> {
+    let (w, h, grid) = (a.width, a.height, grid.max(1));
+    let (cols, rows) = (w.div_ceil(grid), h.div_ceil(grid));
+    if b.width != w || b.height != h { return Ok((cols, rows, vec![true; cols * rows])); }
+    if let Some(m) = mask { m.check(a)?; }
+    let (pa, pb) = (a.pixels(), b.pixels());
+    ensure!(pa.len() >= w * h && pb.len() >= w * h, "Frame {} or {} has fewer pixels than {}x{}", a.frame_number, b.frame_number, w, h);
+    let dirty = (0..cols * rows).map(|cell| {
+        let (x0, y0) = ((cell % cols) * grid, (cell / cols) * grid); let (x1, y1) = ((x0 + grid).min(w), (y0 + grid).min(h));
+        let (mut sum, mut n) = (0u64, 0u64);
+        for y in y0..y1 { for i in y * w + x0..y * w + x1 {
+            if mask.is_some_and(|m| m.data[i] == 0) { continue; }
+            sum += pa[i].abs_diff(pb[i]) as u64; n += 1;
+        } }
+        n > 0 && sum as f64 / n as f64 > threshold
+    }).collect();
+    Ok((cols, rows, dirty))
+}
+
+/// 去掉外接矩形不超过 cursor_size x cursor_size 的连通脏块区域（4 邻接），返回剩余脏块数
+pub fn drop_cursor_regions(cols: usize, rows: usize, dirty: &mut [bool], cursor_size: usize) -> usize {
+    let mut seen = vec![false; dirty.len()]; let mut remaining = 0;
+    for s in 0..dirty.len() {
+        if !dirty[s] || seen[s] { continue; }
+        let (mut stack, mut region) = (vec![s], Vec::new()); seen[s] = true;
+        while let Some(c) = stack.pop() {
+            region.push(c); let (x, y) = (c % cols, c / cols);
+            let neighbours = [(x > 0).then(|| c - 1), (x + 1 < cols).then(|| c + 1), (y > 0).then(|| c - cols), (y + 1 < rows).then(|| c + cols)];
+            for n in neighbours.into_iter().flatten() { if dirty[n] && !seen[n] { seen[n] = true; stack.push(n); } }
+        }
+        let (xs, ys) = (region.iter().map(|c| c % cols), region.iter().map(|c| c / cols));
+        let bw = xs.clone().max().unwrap_or(0) - xs.min().unwrap_or(0) + 1; let bh = ys.clone().max().unwrap_or(0) - ys.min().unwrap_or(0) + 1;
+        if bw <= cursor_size && bh <= cursor_size { for c in region { dirty[c] = false; } } else { remaining += region.len(); }
+    }
+    remaining
+}
+
+/// 逐帧脏块占比（已忽略鼠标区域），ratios[0] = 0
+pub fn slide_change_ratios(frames: &[PyVideoFrame], params: &SlideParams, mask: Option<&PyFrameMask>) -> Result<Vec<f64>> {
+    let changes: Vec<f64> = frames.par_windows(2).map(|p| {
+        let (cols, rows, mut dirty) = change_map(&p[0], &p[1], params.grid, params.change_threshold, mask)?;
+        Ok(drop_cursor_regions(cols, rows, &mut dirty, params.cursor_size) as f64 / (cols * rows).max(1) as f64)
+    }).collect::<Result<_>>()?;
+    let mut ratios = vec![0.0]; ratios.extend(changes);
+    ratios.truncate(frames.len());
+    Ok(ratios)
+}
+
+/// 按换页切分，每页取最后一个连续静止 min_stable_frames 帧的位置；没有静止段的页（切换动画）丢弃
+pub fn detect_slides(ratios: &[f64], params: &SlideParams) -> Vec<Slide> {
+    let mut slides = Vec::new();
+    let mut close = |start: usize, end: usize| {
+        let (mut run, mut keyframe) = (0, None);
+        for (j, &r) in ratios.iter().enumerate().take(end + 1).skip(start) {
+            run = if j > start && r == 0.0 { run + 1 } else { 1 };
+            if run >= params.min_stable_frames.max(1) { keyframe = Some(j); }
+        }
+        if let Some(keyframe) = keyframe { slides.push(Slide { start, end, keyframe, strength: if start == 0 { f64::INFINITY } else { ratios[start] } }); }
+    };
+    if ratios.is_empty() { return slides; }
+    let mut start = 0;
+    for (i, &r) in ratios.iter().enumerate().skip(1) { if r >= params.new_slide_ratio { close(start, i - 1); start = i; } }
+    close(start, ratios.len() - 1);
+    slides
+}
+
+/// 每页一个关键帧；超过 k 页时保留换页变化最大的 k 页，结果按帧号升序
+pub fn select_slides(frames: &[PyVideoFrame], k: usize, params: &SlideParams, mask: Option<&PyFrameMask>) -> Result<Vec<usize>> {
+    let mut slides = detect_slides(&slide_change_ratios(frames, params, mask)?, params);
+    if slides.len() > k {
+        slides.sort_by(|a, b| b.strength.partial_cmp(&a.strength).unwrap_or(std::cmp::Ordering::Equal));
+        slides.truncate(k);
+    }
+    let mut out: Vec<usize> = slides.into_iter().map(|s| s.keyframe).collect();
+    out.sort_unstable();
+    Ok(out)
+}
@@ -0,0 +1,390 @@
+use anyhow::{Context, Result};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyIterator;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use crate::video::animation::{stream_animation, to_gray, AnimationFormat};
+use crate::video::chunked::{read_gray_frames, stream_chunked, GRAY_OUTPUT_ARGS};
+use crate::video::frame::{PyVideoFrame, DEFAULT_FPS};
+use crate::video::input::VideoInput;
+use crate::video::store::FrameData;
+use crate::video::utils::{probe_seekable, VideoInfo};
+
+/// 帧来源：逐帧回调，回调返回 false 时提前结束。返回 (视频信息, 帧数)
+/// 回调拿到的是借用：来源可复用同一帧的缓冲，需要保留时由调用方复制（FrameStore::push 或 clone）
+pub trait FrameSource {
+    /// 日志用的简短描述
+    fn describe(&self) -> String;
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)>;
+    /// 解码自身的并行工作改在指定线程池中执行；来源在调用线程上逐帧回调，无法整体 install 到池中
+    fn use_thread_pool(&mut self, _pool: &Arc<ThreadPool>) {}
+    /// 收集完整帧列表时的常驻内存预算；需要先缓冲帧的来源（分段解码）据此落盘
+    fn use_ram_budget(&mut self, _budget: Option<usize>) {}
+}
+
+/// 按固定帧长从 reader 读取灰度帧，每帧读入同一个复用的帧
+fn stream_raw<R: Read>(reader: &mut R, info: VideoInfo, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> usize {
+    let mut frame = PyVideoFrame::blank(info.width, info.height); let mut count = 0;
+    while reader.read_exact(frame.pixels_mut()).is_ok() {
+        (frame.frame_number, frame.timestamp) = (count, count as f64 / info.fps);
+        let keep_going = on_frame(&frame); count += 1;
+        if !keep_going { break; }
+    }
+    count
+}
+
+/// ffmpeg 子进程解码为 gray rawvideo，帧时间戳取自 showinfo 的 pts；内存输入经 stdin 传入
+/// `workers` > 1 时按时间分段由多个 ffmpeg 并发解码（见 `stream_chunked`）
+pub struct FfmpegSource { pub input: VideoInput, pub ffmpeg_path: PathBuf, pub workers: usize, pub ram_budget: Option<usize> }
+
+impl FrameSource for FfmpegSource {
+    fn describe(&self) -> String { if self.workers > 1 { format!("ffmpeg x{}:{}", self.workers, self.input.display_name()) } else { format!("ffmpeg:{}", self.input.display_name()) } }
+    fn use_ram_budget(&mut self, budget: Option<usize>) { self.ram_budget = budget; }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+        if self.workers > 1 { if let Some(r) = stream_chunked(&self.input, &self.ffmpeg_path, self.workers, self.ram_budget, on_frame)? { return Ok(r); } }
+        let (info, input, _spilled) = probe_seekable(&self.input, &self.ffmpeg_path)?;
+        let mut cmd = Command::new(&self.ffmpeg_path); cmd.args(["-hide_banner", "-nostats"]); input.add_ffmpeg_input(&mut cmd); cmd.args(["-threads", "0"]).args(GRAY_OUTPUT_ARGS);
+        let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().context("Failed to spawn FFmpeg process")?; let writer = input.feed(&mut child);
+        let mut frame = PyVideoFrame::blank(info.width, info.height); let mut count = 0;
+        let result = read_gray_frames(&mut child, &mut frame, 0.0, |f| { count += 1; Ok(on_frame(f)) });
+        if let Some(w) = writer { let _ = w.join(); }
+        result?; Ok((info, count))
+    }
+}
+
+/// GIF/APNG/WebP 在进程内解码
+pub struct AnimationSource { pub input: VideoInput, pub format: AnimationFormat }
+
+impl FrameSource for AnimationSource {
+    fn describe(&self) -> String { format!("{:?}:{}", self.format, self.input.display_name()) }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> { stream_animation(&self.input, self.format, on_frame) }
+}
+
+/// YUV4MPEG2：只读取 Y 平面，跳过色度与 alpha 平面
+pub struct Y4mSource { pub input: VideoInput }
+
+/// 每帧 Y 平面之后的额外样本数（色度 + alpha）与位深
+fn y4m_layout(colorspace: &str, w: usize, h: usize) -> Result<(usize, u32)> {
+    let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+    // 高位深写作 C420p10 / C444p16 / Cmono16
+    let (base, depth) = match colorspace.rsplit_once('p').and_then(|(b, d)| d.parse::<u32>().ok().map(|d| (b, d))) {
+        Some(v) => v,
+        None => (colorspace, if colorspace == "mono16" { 16 } else { 8 }),
+    };
+    let extra = match base {
+        c if c.starts_with("mono") => 0,
+        "444alpha" => 3 * w * h,
+        "444" => 2 * w * h,
+        "422" => 2 * cw * h,
+        "411" => 2 * w.div_ceil(4) * h,
+        c if c.starts_with("420") => 2 * cw * ch,
+        other => anyhow::bail!("Unsupported Y4M colorspace C{}", other),
+    };
+    Ok((extra, depth))
+}
+
+impl FrameSource for Y4mSource {
+    fn describe(&self) -> String { format!("y4m:{}", self.input.display_name()) }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+        let mut reader = self.input.reader()?;
+        let mut header = String::new(); reader.read_line(&mut header).context("Failed to read Y4M header")?;
+        let mut tokens = header.split_whitespace();
+        if tokens.next() != Some("YUV4MPEG2") { anyhow::bail!("Not a Y4M file: {}", self.input.display_name()); }
+        let (mut w, mut h, mut fps, mut colorspace) = (0usize, 0usize, DEFAULT_FPS, "420jpeg".to_string());
+        for t in tokens {
+            let (tag, v) = t.split_at(1);
+            match tag {
+                "W" => w = v.parse().context("Bad Y4M width")?,
+                "H" => h = v.parse().context("Bad Y4M height")?,
+                "F" => if let Some((n, d)) = v.split_once(':') { if let (Ok(n), Ok(d)) = (n.parse::<f64>(), d.parse::<f64>()) { if n > 0.0 && d > 0.0 { fps = n / d; } } },
+                "C" => colorspace = v.to_string(),
+                _ => {}
+            }
+        }
+        if w == 0 || h == 0 { anyhow::bail!("Y4M header missing dimensions"); }
+        let (extra, depth) = y4m_layout(&colorspace, w, h)?; let bytes = if depth > 8 { 2 } else { 1 };
+        // 8 位直接读入复用的帧；高位深先读入 wide 再逐样本缩放
+        let mut frame = PyVideoFrame::blank(w, h); let mut wide = vec![0u8; if bytes == 2 { w * h * 2 } else { 0 }];
+        let mut skip = vec![0u8; extra * bytes]; let mut line = String::new(); let mut count = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || !line.starts_with("FRAME") { break; }
+            let luma = if bytes == 1 { frame.pixels_mut() } else { &mut wide[..] };
+            if reader.read_exact(luma).is_err() || reader.read_exact(&mut skip).is_err() { break; }
+            if bytes == 2 { for (d, s) in frame.pixels_mut().iter_mut().zip(wide.chunks_exact(2)) { *d = (u16::from_le_bytes([s[0], s[1]]) >> (depth - 8)) as u8; } }
+            (frame.frame_number, frame.timestamp) = (count, count as f64 / fps);
+            let keep_going = on_frame(&frame); count += 1;
+            if !keep_going { break; }
+        }
+        Ok((VideoInfo { width: w, height: h, fps }, count))
+    }
+}
+
+/// 图片序列目录（按文件名排序），所有图片尺寸须一致
+pub struct ImageDirSource { pub dir: PathBuf, pub fps: f64, pub pool: Option<Arc<ThreadPool>> }
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+
+impl FrameSource for ImageDirSource {
+    fn describe(&self) -> String { format!("images:{}", self.dir.display()) }
+    fn use_thread_pool(&mut self, pool: &Arc<ThreadPool>) { self.pool = Some(pool.clone()); }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir).with_context(|| format!("Failed to read directory {}", self.dir.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().and_then(|e| e.to_str()).is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str())))
+            .collect();
+        files.sort();
+        if files.is_empty() { anyhow::bail!("No images found in {}", self.dir.display()); }
+        let mut info: Option<VideoInfo> = None; let mut count = 0;
+        // 按批并行解码，按顺序回调
+        for batch in files.chunks(32) {
+            let decode = || -> Vec<Result<(usize, usize, Vec<u8>)>> { batch.par_iter().map(|p| {
+                let img = image::open(p).with_context(|| format!("Failed to decode {}", p.display()))?.to_rgba8();
+                Ok((img.width() as usize, img.height() as usize, to_gray(&img)))
+            }).collect() };
+            let decoded = match &self.pool { Some(pool) => pool.install(decode), None => decode() };
+            for (p, d) in batch.iter().zip(decoded) {
+                let (w, h, data) = d?;
+                let i = *info.get_or_insert(VideoInfo { width: w, height: h, fps: self.fps });
+                if (w, h) != (i.width, i.height) { anyhow::bail!("Image {} is {}x{} but previous images are {}x{}", p.display(), w, h, i.width, i.height); }
+                let keep_going = on_frame(&PyVideoFrame::from_data(count, w, h, FrameData::Owned(data), count as f64 / self.fps)); count += 1;
+                if !keep_going { return Ok((i, count)); }
+            }
+        }
+        Ok((info.unwrap_or(VideoInfo { width: 0, height: 0, fps: self.fps }), count))
+    }
+}
+
+/// 无文件头的连续 8 位灰度帧
+pub struct RawGraySource { pub path: PathBuf, pub width: usize, pub height: usize, pub fps: f64 }
+
+impl FrameSource for RawGraySource {
+    fn describe(&self) -> String { format!("gray:{} ({}x{})", self.path.display(), self.width, self.height) }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+        if self.width == 0 || self.height == 0 { anyhow::bail!("Raw gray source needs non-zero dimensions"); }
+        let info = VideoInfo { width: self.width, height: self.height, fps: self.fps };
+        let mut reader = BufReader::with_capacity(1024*1024, File::open(&self.path).with_context(|| format!("Failed to open {}", self.path.display()))?);
+        Ok((info, stream_raw(&mut reader, info, on_frame)))
+    }
+}
+
+/// Python 可迭代对象，元素为 PyVideoFrame；fps 由首末时间戳估计
+pub struct PyIterSource<'py> { pub iter: Bound<'py, PyIterator> }
+
+impl FrameSource for PyIterSource<'_> {
+    fn describe(&self) -> String { "python iterable".into() }
+    fn stream(&mut self, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+        stream_iter(self.iter.clone().map(|item| item.and_then(|o| o.extract()).map_err(|e| anyhow::anyhow!("Invalid frame from iterable: {}", e))), on_frame)
+    }
+}
+
+/// 逐帧转发现成的帧：校验尺寸一致，fps 由首末时间戳估计
+fn stream_iter(frames: impl Iterator<Item = Result<PyVideoFrame>>, on_frame: &mut dyn FnMut(&PyVideoFrame) -> bool) -> Result<(VideoInfo, usize)> {
+    let (mut info, mut count, mut first_ts, mut last_ts) = (None::<VideoInfo>, 0, 0.0, 0.0);
+    for frame in frames {
+        let frame = frame?;
+        let i = *info.get_or_insert(VideoInfo { width: frame.width, height: frame.height, fps: DEFAULT_FPS });
+        if (frame.width, frame.height) != (i.width, i.height) { anyhow::bail!("Frame {} is {}x{} but previous frames are {}x{}", count, frame.width, frame.height, i.width, i.height); }
+        if count == 0 { first_ts = frame.timestamp; } last_ts = frame.timestamp; count += 1;
+        if !on_frame(&frame) { break; }
+    }
+    let mut info = info.unwrap_or(VideoInfo { width: 0, height: 0, fps: DEFAULT_FPS });
+    if count > 1 && last_ts > first_ts { info.fps = (count - 1) as f64 / (last_ts - first_ts); }
+    Ok((info, count))
+}
+
+/// 自动选择：目录 -> 图片序列；Y4M/动图按文件头识别；其余交给 ffmpeg
+/// `workers` 为 ffmpeg 解码进程数，只对交给 ffmpeg 的输入生效
+pub fn open_source(input: &VideoInput, ffmpeg_path: &Path, workers: usize) -> Box<dyn FrameSource> {
+    if let VideoInput::Path(p) = input { if p.is_dir() { return Box::new(ImageDirSource { dir: p.clone(), fps: DEFAULT_FPS, pool: None }); } }
+    let head = input.head(12);
+    if head.starts_with(b"YUV4MPEG2") { return Box::new(Y4mSource { input: input.clone() }); }
+    if let Some(format) = AnimationFormat::sniff(&head) { return Box::new(AnimationSource { input: input.clone(), format }); }
+    Box::new(FfmpegSource { input: input.clone(), ffmpeg_path: ffmpeg_path.to_path_buf(), workers, ram_budget: None })
+}
+
+enum SourceSpec { Auto(PathBuf), Ffmpeg(PathBuf, Option<usize>), Y4m(PathBuf), ImageDir(PathBuf, f64), RawGray(PathBuf, usize, usize, f64), Frames(PyObject) }
+
+/// Python 侧的帧来源描述；真正打开延迟到调用提取方法时
+#[pyclass]
+pub struct PyFrameSource { spec: SourceSpec }
+
+#[pymethods]
+impl PyFrameSource {
+    /// 按路径自动识别（目录/Y4M/动图/其他交给 ffmpeg）
+    #[staticmethod]
+    fn from_path(path: PathBuf) -> Self { Self { spec: SourceSpec::Auto(path) } }
+    /// workers 指定并发解码进程数，缺省时沿用提取器的 decode_workers
+    #[staticmethod]
+    #[pyo3(signature = (path, workers=None))]
+    fn ffmpeg(path: PathBuf, workers: Option<usize>) -> Self { Self { spec: SourceSpec::Ffmpeg(path, workers) } }
+    #[staticmethod]
+    fn y4m(path: PathBuf) -> Self { Self { spec: SourceSpec::Y4m(path) } }
+    #[staticmethod]
+    #[pyo3(signature = (path, fps=None))]
+    fn image_dir(path: PathBuf, fps: Option<f64>) -> Self { Self { spec: SourceSpec::ImageDir(path, fps.unwrap_or(DEFAULT_FPS)) } }
+    #[staticmethod]
+    #[pyo3(signature = (path, width, height, fps=None))]
+    fn raw_gray(path: PathBuf, width: usize, height: usize, fps: Option<f64>) -> Self { Self { spec: SourceSpec::RawGray(path, width, height, fps.unwrap_or(DEFAULT_FPS)) } }
+    /// 任意可迭代的 PyVideoFrame（列表、生成器等），只消费一次
+    #[staticmethod]
+    fn from_frames(frames: PyObject) -> Self { Self { spec: SourceSpec::Frames(frames) } }
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.spec { SourceSpec::Auto(_) => "auto", SourceSpec::Ffmpeg(..) => "ffmpeg", SourceSpec::Y4m(_) => "y4m", SourceSpec::ImageDir(..) => "image_dir", SourceSpec::RawGray(..) => "raw_gray", SourceSpec::Frames(_) => "frames" }
+    }
+    fn __repr__(&self) -> String {
+        match &self.spec {
+            SourceSpec::Frames(_) => "PyFrameSource(kind='frames')".into(),
+            SourceSpec::Auto(p) | SourceSpec::Ffmpeg(p, _) | SourceSpec::Y4m(p) | SourceSpec::ImageDir(p, _) | SourceSpec::RawGray(p, ..) => format!("PyFrameSource(kind='{}', path='{}')", self.kind(), p.display()),
+        }
+    }
+}
+
+impl PyFrameSource {
+    pub fn open<'py>(&self, py: Python<'py>, ffmpeg_path: &Path, workers: usize) -> PyResult<Box<dyn FrameSource + 'py>> {
+        Ok(match &self.spec {
+            SourceSpec::Auto(p) => open_source(&VideoInput::Path(p.clone()), ffmpeg_path, workers),
+            SourceSpec::Ffmpeg(p, w) => Box::new(FfmpegSource { input: VideoInput::Path(p.clone()), ffmpeg_path: ffmpeg_path.to_path_buf(), workers: w.unwrap_or(workers), ram_budget: None }),
+            SourceSpec::Y4m(p) => Box::new(Y4mSource { input: VideoInput::Path(p.clone()) }),
+            SourceSpec::ImageDir(p, fps) => Box::new(ImageDirSource { dir: p.clone(), fps: *fps, pool: None }),
+            SourceSpec::RawGray(p, w, h, fps) => Box::new(RawGraySource { path: p.clone(), width: *w, height: *h, fps: *fps }),
+            SourceSpec::Frames(obj) => Box::new(PyIterSource { iter: obj.bind(py).iter()? }),
+        })
+    }
+    /// 可重新读取的视频输入（保存关键帧等需要再次解码的场景）；图片序列/裸帧/Python 帧没有对应的视频文件
+    pub fn input(&self) -> Option<VideoInput> {
+        match &self.spec { SourceSpec::Auto(p) | SourceSpec::Ffmpeg(p, _) | SourceSpec::Y4m(p) => Some(VideoInput::Path(p.clone())), _ => None }
+    }
+}
+
+/// 带 read() 方法的 Python 文件对象（二进制模式）
+pub struct FileLike<'py>(Bound<'py, PyAny>);
+
+impl<'py> FromPyObject<'py> for FileLike<'py> {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if ob.hasattr("read")? { Ok(Self(ob.clone())) } else { Err(PyTypeError::new_err("expected a readable binary file object")) }
+    }
+}
+
+/// 提取方法的输入：PyFrameSource、bytes/bytearray/memoryview、路径（str/PathLike，可含非 UTF-8 字节）或文件对象。
+/// bytes 须排在路径之前，否则会被 os.fspath 当作路径。
+#[derive(FromPyObject)]
+pub enum SourceArg<'py> {
+    Source(PyRef<'py, PyFrameSource>),
+    Data(PyBuffer<u8>),
+    Path(PathBuf),
+    File(FileLike<'py>),
+}
+
+impl SourceArg<'_> {
+    pub fn open<'py>(&self, py: Python<'py>, ffmpeg_path: &Path, workers: usize) -> PyResult<Box<dyn FrameSource + 'py>> {
+        match self { SourceArg::Source(s) => s.open(py, ffmpeg_path, workers), _ => Ok(open_source(&self.input(py)?, ffmpeg_path, workers)) }
+    }
+    /// 文件对象一次性读入内存（与 bytes 输入相同处理）
+    pub fn input(&self, py: Python<'_>) -> PyResult<VideoInput> {
+        match self {
+            SourceArg::Source(s) => s.input().ok_or_else(|| PyValueError::new_err(format!("{} has no underlying video file; pass a path, bytes or file object", s.__repr__()))),
+            SourceArg::Data(buf) => Ok(buf.to_vec(py)?.into()),
+            SourceArg::Path(p) => Ok(VideoInput::Path(p.clone())),
+            SourceArg::File(f) => Ok(f.0.call_method0("read")?.extract::<PyBuffer<u8>>()?.to_vec(py)?.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn y4m(header: &str, frames: &[&[u8]]) -> VideoInput {
+        let mut d = format!("{}\n", header).into_bytes();
+        for f in frames { d.extend_from_slice(b"FRAME\n"); d.extend_from_slice(f); }
+        d.into()
+    }
+
+    fn collect(source: &mut dyn FrameSource) -> Result<(VideoInfo, Vec<PyVideoFrame>)> {
+        let mut frames = Vec::new(); let (info, count) = source.stream(&mut |f| { frames.push(f.clone()); true })?;
+        assert_eq!(count, frames.len()); Ok((info, frames))
+    }
+
+    #[test]
+    fn y4m_reads_luma_and_skips_chroma() {
+        // 4x2 420：Y 8 字节 + U/V 各 2x1
+        let f0: Vec<u8> = (0..8).chain([100, 101, 102, 103]).collect(); let f1: Vec<u8> = (10..18).chain([0; 4]).collect();
+        let (info, frames) = collect(&mut Y4mSource { input: y4m("YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420jpeg", &[&f0, &f1]) }).unwrap();
+        assert_eq!((info.width, info.height, info.fps), (4, 2, 25.0));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].pixels(), &f0[..8]); assert_eq!(frames[1].pixels(), &f1[..8]);
+        assert_eq!(frames[1].timestamp, 1.0 / 25.0);
+    }
+
+    #[test]
+    fn y4m_high_bit_depth_is_scaled_to_8_bit() {
+        let frame: Vec<u8> = [0xffffu16, 0x8000].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let (_, frames) = collect(&mut Y4mSource { input: y4m("YUV4MPEG2 W2 H1 Cmono16", &[&frame]) }).unwrap();
+        assert_eq!(frames[0].pixels(), &[0xff, 0x80]);
+    }
+
+    #[test]
+    fn y4m_stops_at_truncated_frame() {
+        let (_, frames) = collect(&mut Y4mSource { input: y4m("YUV4MPEG2 W2 H2 Cmono", &[&[1, 2, 3, 4], &[5, 6]]) }).unwrap();
+        assert_eq!(frames.len(), 1);
+    }
+
+    #[test]
+    fn y4m_rejects_bad_headers() {
+        let err = |header: &str| collect(&mut Y4mSource { input: y4m(header, &[]) }).map(|_| ()).expect_err("header should be rejected").to_string();
+        assert!(err("RIFF W2 H2").starts_with("Not a Y4M file"));
+        assert!(err("YUV4MPEG2 W2 F25:1").contains("missing dimensions"));
+        assert!(err("YUV4MPEG2 Wx H2").contains("Bad Y4M width"));
+        assert!(err("YUV4MPEG2 W2 H2 Cxyz").contains("Unsupported Y4M colorspace"));
+    }
+
+    #[test]
+    fn raw_gray_drops_truncated_tail() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&(0..15u8).collect::<Vec<_>>()).unwrap();
+        let mut source = RawGraySource { path: file.path().to_path_buf(), width: 3, height: 2, fps: 10.0 };
+        let (info, frames) = collect(&mut source).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].pixels(), &[6, 7, 8, 9, 10, 11]);
+        assert_eq!((frames[1].frame_number, frames[1].timestamp), (1, 0.1));
+    }
+
+    #[test]
+    fn raw_gray_rejects_zero_dimensions() {
+        assert!(collect(&mut RawGraySource { path: PathBuf::from("unused"), width: 0, height: 2, fps: 10.0 }).is_err());
+    }
+
+    fn frame(n: usize, w: usize, h: usize, ts: f64) -> Result<PyVideoFrame> { Ok(PyVideoFrame::from_data(n, w, h, FrameData::Owned(vec![0; w * h]), ts)) }
+
+    #[test]
+    fn iterable_estimates_fps_from_timestamps() {
+        let mut seen = 0;
+        let (info, count) = stream_iter((0..5).map(|i| frame(i, 2, 2, i as f64 * 0.5)), &mut |_| { seen += 1; true }).unwrap();
+        assert_eq!((info.width, info.height, info.fps, count, seen), (2, 2, 2.0, 5, 5));
+    }
+
+    #[test]
+    fn iterable_rejects_mismatched_dimensions() {
+        let err = stream_iter([frame(0, 2, 2, 0.0), frame(1, 3, 2, 0.1)].into_iter(), &mut |_| true).unwrap_err();
+        assert!(err.to_string().contains("Frame 1 is 3x2"));
+    }
+
+    #[test]
+    fn iterable_propagates_errors_and_early_stop() {
+        assert!(stream_iter([frame(0, 2, 2, 0.0), Err(anyhow::anyhow!("boom"))].into_iter(), &mut |_| true).is_err());
+        let (_, count) = stream_iter((0..5).map(|i| frame(i, 2, 2, i as f64)), &mut |f| f.frame_number < 1).unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use image::RgbImage;
+use pyo3::prelude::*;
+use std::fmt::Write as _;
+use rayon::ThreadPool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::video::encode::{decode_keyframe_images, encode_image, DecodeSize, EncodeParams};
+use crate::video::input::VideoInput;
+use crate::video::utils::stream_frames;
+
+/// 播放器拖动预览用的雪碧图与 WebVTT 索引
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PySpriteSheet {
+    #[pyo3(get)] pub vtt_path: PathBuf,
+    #[pyo3(get)] pub sprite_paths: Vec<PathBuf>,
+    #[pyo3(get)] pub frame_indices: Vec<usize>,
+    #[pyo3(get)] pub timestamps: Vec<f64>,
+    #[pyo3(get)] pub tile_width: u32,
+    #[pyo3(get)] pub tile_height: u32,
+    #[pyo3(get)] pub columns: usize,
+}
+
+#[pymethods]
+impl PySpriteSheet {
+    fn __len__(&self) -> usize { self.frame_indices.len() }
+    fn __repr__(&self) -> String { format!("PySpriteSheet({} thumbnails {}x{}, {} sheets, vtt='{}')", self.frame_indices.len(), self.tile_width, self.tile_height, self.sprite_paths.len(), self.vtt_path.display()) }
+}
+
+/// 缩略图取样方式
+#[derive(Debug, Clone)]
+pub enum SpriteTimes {
+    /// 每隔固定秒数取一帧
+    Interval(f64),
+    /// 指定帧（通常为 extract_keyframes 的结果）
+    Frames(Vec<usize>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SpriteParams {
+    pub tile_width: u32,
+    /// 0 表示按视频宽高比推算
+    pub tile_height: u32,
+    pub columns: usize,
+    /// 每张雪碧图的行数上限，超出后拆分到下一张
+    pub rows: usize,
+    /// 输出文件名前缀：{prefix}_000.jpg ... 与 {prefix}.vtt
+    pub prefix: String,
+    /// VTT 中图片地址的前缀（如 "/static/thumbs/"），默认与 VTT 同目录的相对路径
+    pub url_prefix: String,
+    pub encode: EncodeParams,
+}
+
+impl Default for SpriteParams {
+    fn default() -> Self { Self { tile_width: 160, tile_height: 0, columns: 10, rows: 10, prefix: "sprites".into(), url_prefix: String::new(), encode: EncodeParams::default() } }
+}
+
+/// WebVTT 时间戳 HH:MM:SS.mmm
+fn vtt_time(secs: f64) -> String {
+    let ms = (secs.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// 每个区间取时间戳首个落入的帧
+fn interval_picks(timestamps: &[f64], interval: f64) -> Vec<usize> {
+    let (mut picks, mut next) = (Vec::new(), 0.0);
+    for (i, &t) in timestamps.iter().enumerate() {
+        if t + 1e-6 >= next { picks.push(i); next = (((t + 1e-6) / interval).floor() + 1.0) * interval; }
+    }
+    picks
+}
+
+/// 先快速流式解码一遍取得每帧时间戳与总时长，再按需解码选中帧的缩略图
+#[allow(clippy::too_many_arguments)]
+pub fn generate_sprites(input: &VideoInput, ffmpeg_path: &Path, out_dir: &Path, times: &SpriteTimes, params: &SpriteParams, workers: usize, pool: &Arc<ThreadPool>, verbose: bool) -> Result<PySpriteSheet> {
+    let mut timestamps = Vec::new();
+    let (info, count) = stream_frames(input, ffmpeg_path, workers, pool, verbose, |f| { timestamps.push(f.timestamp); true })?;
+    let duration = timestamps.last().map_or(0.0, |t| t + 1.0 / info.fps);
+    let picks = match times {
+        SpriteTimes::Interval(secs) => interval_picks(&timestamps, *secs),
+        SpriteTimes::Frames(indices) => { let mut v: Vec<usize> = indices.iter().copied().filter(|&i| i < count).collect(); v.sort_unstable(); v.dedup(); v }
+    };
+    if picks.is_empty() { anyhow::bail!("No frames selected for sprites"); }
+    let tw = params.tile_width.max(1);
+    let th = if params.tile_height > 0 { params.tile_height } else { ((tw as f64 * info.height as f64 / info.width.max(1) as f64).round() as u32).max(1) };
+    let thumbs = decode_keyframe_images(input, ffmpeg_path, &picks, None, DecodeSize::Exact(tw, th))?;
+    if thumbs.is_empty() { anyhow::bail!("No thumbnails decoded for sprites"); }
+
+    std::fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    let ext = params.encode.format.extension();
+    let encode = EncodeParams { max_dimension: 0, max_bytes: 0, ..params.encode.clone() };
+    let (columns, per_sheet) = (params.columns.max(1), params.columns.max(1) * params.rows.max(1));
+    let (mut sprite_paths, mut vtt) = (Vec::new(), String::from("WEBVTT\n"));
+    for (sheet_idx, chunk) in thumbs.chunks(per_sheet).enumerate() {
+        let cols = chunk.len().min(columns);
+        let mut sheet = RgbImage::new(cols as u32 * tw, chunk.len().div_ceil(columns) as u32 * th);
+        let name = format!("{}_{:03}.{}", params.prefix, sheet_idx, ext);
+        for (k, (idx, _, thumb)) in chunk.iter().enumerate() {
+            let (x, y) = ((k % columns) as u32 * tw, (k / columns) as u32 * th);
+            image::imageops::replace(&mut sheet, thumb, x as i64, y as i64);
+            // 首条从 0 开始、每条持续到下一张缩略图，末条到视频结尾
+            let global = sheet_idx * per_sheet + k;
+            let start = if global == 0 { 0.0 } else { timestamps[*idx] };
+            let end = thumbs.get(global + 1).map_or(duration, |(next, _, _)| timestamps[*next]);
+            let _ = write!(vtt, "\n{} --> {}\n{}{}#xywh={},{},{},{}\n", vtt_time(start), vtt_time(end), params.url_prefix, name, x, y, tw, th);
+        }
+        let path = out_dir.join(&name);
+        std::fs::write(&path, encode_image(sheet, &encode)?.0).with_context(|| format!("Failed to write {}", path.display()))?;
+        sprite_paths.push(path);
+    }
+    let vtt_path = out_dir.join(format!("{}.vtt", params.prefix));
+    std::fs::write(&vtt_path, vtt).with_context(|| format!("Failed to write {}", vtt_path.display()))?;
+    Ok(PySpriteSheet { vtt_path, sprite_paths, frame_indices: thumbs.iter().map(|t| t.0).collect(), timestamps: thumbs.iter().map(|t| timestamps[t.0]).collect(), tile_width: tw, tile_height: th, columns })
+}
@@ -0,0 +1,141 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::quality::histogram_entropy;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// 单帧统计量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub mean: f64,
+    pub variance: f64,
+    pub min: u8,
+    pub max: u8,
+    pub entropy: f64,
+    /// 梯度 |gx|+|gy| 超过阈值的像素占比
+    pub edge_density: f64,
+}
+
+/// 逐帧统计时间序列（列式），便于直接画亮度曲线或做静止段分析
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct PyFrameStats {
+    #[pyo3(get)] pub frame_indices: Vec<usize>,
+    #[pyo3(get)] pub timestamps: Vec<f64>,
+    #[pyo3(get)] pub mean: Vec<f64>,
+    #[pyo3(get)] pub variance: Vec<f64>,
+    #[pyo3(get)] pub min: Vec<u8>,
+    #[pyo3(get)] pub max: Vec<u8>,
+    #[pyo3(get)] pub entropy: Vec<f64>,
+    #[pyo3(get)] pub edge_density: Vec<f64>,
+}
+
+#[pymethods]
+impl PyFrameStats {
+    fn __len__(&self) -> usize { self.frame_indices.len() }
+    fn to_dict(&self) -> PyResult<HashMap<String, PyObject>> { Python::with_gil(|py| { let mut d = HashMap::new();
+        d.insert("frame_indices".into(), self.frame_indices.to_object(py));
+        d.insert("timestamps".into(), self.timestamps.to_object(py));
+        d.insert("mean".into(), self.mean.to_object(py));
+        d.insert("variance".into(), self.variance.to_object(py));
+        d.insert("min".into(), self.min.to_object(py));
+        d.insert("max".into(), self.max.to_object(py));
+        d.insert("entropy".into(), self.entropy.to_object(py));
+        d.insert("edge_density".into(), self.edge_density.to_object(py)); Ok(d) }) }
+    fn __repr__(&self) -> String { format!("PyFrameStats(frames={})", self.frame_indices.len()) }
+}
+
+impl PyFrameStats {
+    pub fn push(&mut self, frame: &PyVideoFrame, s: FrameStats) {
+        self.frame_indices.push(frame.frame_number); self.timestamps.push(frame.timestamp);
+        self.mean.push(s.mean); self.variance.push(s.variance); self.min.push(s.min); self.max.push(s.max);
+        self.entropy.push(s.entropy); self.edge_density.push(s.edge_density);
+    }
+}
+
+pub const DEFAULT_EDGE_THRESHOLD: u32 = 32;
+
+/// 逐行遍历：直方图（均值/方差/极值/熵）标量累加，边缘计数按行走 SIMD 内核
+pub fn compute_frame_stats(frame: &PyVideoFrame, edge_threshold: u32) -> FrameStats {
+    let (w, h, p) = (frame.width, frame.height, frame.pixels());
+    if p.is_empty() { return FrameStats::default(); }
+    let mut hist = [0u64; 256]; let mut edges = 0u64;
+    for y in 0..h {
+        let row = &p[y * w..(y + 1) * w];
+        for &v in row { hist[v as usize] += 1; }
+        if y + 1 < h { edges += edge_count_row(row, &p[(y + 1) * w..(y + 2) * w], edge_threshold); }
+    }
+    let n = p.len() as f64;
+    let (mut sum, mut sum_sq) = (0f64, 0f64);
+    for (v, &c) in hist.iter().enumerate() { let c = c as f64; sum += v as f64 * c; sum_sq += (v * v) as f64 * c; }
+    let mean = sum / n;
+    let interior = ((w.saturating_sub(1)) * (h.saturating_sub(1))).max(1) as f64;
+    FrameStats {
+        mean, variance: (sum_sq / n - mean * mean).max(0.0),
+        min: hist.iter().position(|&c| c > 0).unwrap_or(0) as u8,
+        max: hist.iter().rposition(|&c| c > 0).unwrap_or(0) as u8,
+        entropy: histogram_entropy(&hist, p.len()),
+        edge_density: edges as f64 / interior,
+    }
+}
+
+/// 一行中 |gx|+|gy| > threshold 的像素数（最后一列没有右邻，不计）
+fn edge_count_row(row: &[u8], below: &[u8], threshold: u32) -> u64 {
+    // 梯度和不超过 510，阈值截断后可按 i16 比较
+    let t = threshold.min(510) as i16;
+    #[cfg(target_arch="x86_64")] unsafe {
+        if std::arch::is_x86_feature_detected!("avx2") { return edge_count_avx2(row, below, t); }
+        if std::arch::is_x86_feature_detected!("sse2") { return edge_count_sse2(row, below, t); }
+    }
+    edge_count_scalar(row, below, 0, t)
+}
+
+fn edge_count_scalar(row: &[u8], below: &[u8], from: usize, t: i16) -> u64 {
+    (from..row.len().saturating_sub(1)).filter(|&x| (row[x].abs_diff(row[x + 1]) as i16 + row[x].abs_diff(below[x]) as i16) > t).count() as u64
+}
+
+/// 每次 16 像素：u8 绝对差 subs(a,b)|subs(b,a)，扩展为 i16 相加后与阈值比较
+#[cfg(target_arch="x86_64")]
+#[target_feature(enable="avx2")]
+unsafe fn edge_count_avx2(row: &[u8], below: &[u8], t: i16) -> u64 {
+    let chunks = row.len().saturating_sub(1) / 16; let vt = _mm256_set1_epi16(t); let mut edges = 0u64;
+    for i in 0..chunks { let off = i * 16;
+        let a = _mm_loadu_si128(row.as_ptr().add(off) as *const __m128i);
+        let n = _mm_loadu_si128(row.as_ptr().add(off + 1) as *const __m128i);
+        let b = _mm_loadu_si128(below.as_ptr().add(off) as *const __m128i);
+        let gx = _mm_or_si128(_mm_subs_epu8(a, n), _mm_subs_epu8(n, a)); let gy = _mm_or_si128(_mm_subs_epu8(a, b), _mm_subs_epu8(b, a));
+        let g = _mm256_add_epi16(_mm256_cvtepu8_epi16(gx), _mm256_cvtepu8_epi16(gy));
+        edges += (_mm256_movemask_epi8(_mm256_cmpgt_epi16(g, vt)) as u32).count_ones() as u64 / 2;
+    }
+    edges + edge_count_scalar(row, below, chunks * 16, t)
+}
+
+#[cfg(target_arch="x86_64")]
+#[target_feature(enable="sse2")]
+unsafe fn edge_count_sse2(row: &[u8], below: &[u8], t: i16) -> u64 {
+    let chunks = row.len().saturating_sub(1) / 16; let (vt, zero) = (_mm_set1_epi16(t), _mm_setzero_si128()); let mut edges = 0u64;
+    for i in 0..chunks { let off = i * 16;
+        let a = _mm_loadu_si128(row.as_ptr().add(off) as *const __m128i);
+        let n = _mm_loadu_si128(row.as_ptr().add(off + 1) as *const __m128i);
+        let b = _mm_loadu_si128(below.as_ptr().add(off) as *const __m128i);
+        let gx = _mm_or_si128(_mm_subs_epu8(a, n), _mm_subs_epu8(n, a)); let gy = _mm_or_si128(_mm_subs_epu8(a, b), _mm_subs_epu8(b, a));
+        let lo = _mm_add_epi16(_mm_unpacklo_epi8(gx, zero), _mm_unpacklo_epi8(gy, zero));
+        let hi = _mm_add_epi16(_mm_unpackhi_epi8(gx, zero), _mm_unpackhi_epi8(gy, zero));
+        edges += ((_mm_movemask_epi8(_mm_cmpgt_epi16(lo, vt)) as u32).count_ones() + (_mm_movemask_epi8(_mm_cmpgt_epi16(hi, vt)) as u32).count_ones()) as u64 / 2;
+    }
+    edges + edge_count_scalar(row, below, chunks * 16, t)
+}
+
+pub fn collect_frame_stats(frames: &[PyVideoFrame], edge_threshold: u32) -> PyFrameStats {
+    let stats: Vec<FrameStats> = frames.par_iter().map(|f| compute_frame_stats(f, edge_threshold)).collect();
+    let mut out = PyFrameStats::default();
+    for (f, s) in frames.iter().zip(stats) { out.push(f, s); }
+    out
+}
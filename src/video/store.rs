@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::video::frame::PyVideoFrame;
+
+/// 内存 arena 单块大小；块一经分配不再扩容，帧以偏移引用
+const ARENA_BLOCK: usize = 64 * 1024 * 1024;
+
+/// 多帧共享的连续存储：内存 arena 块或落盘文件的 mmap
+#[derive(Debug)]
+pub enum Block { Heap(Vec<u8>), Mapped(Mmap) }
+
+impl Deref for Block {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] { match self { Block::Heap(v) => v, Block::Mapped(m) => m } }
+}
+
+/// 帧像素的存放位置：独占缓冲，或共享存储块中的一段（克隆只增加引用计数）
+#[derive(Debug, Clone)]
+pub enum FrameData {
+    Owned(Vec<u8>),
+    Shared { block: Arc<Block>, offset: usize, len: usize },
+}
+
+impl Deref for FrameData {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self { FrameData::Owned(v) => v, FrameData::Shared { block, offset, len } => &block[*offset..*offset + *len] }
+    }
+}
+
+impl FrameData {
+    pub fn is_mapped(&self) -> bool { matches!(self, FrameData::Shared { block, .. } if matches!(**block, Block::Mapped(_))) }
+}
+
+/// 帧在 finish 前的位置：arena 块序号或落盘文件，以及偏移和长度；已位于共享块中的帧直接沿用
+enum Slot { Heap(usize, usize, usize), Spill(usize, usize), Shared(FrameData) }
+
+/// 收集完整帧列表：像素逐帧复制进连续的 arena 块，来源得以复用同一个解码缓冲，避免逐帧分配；
+/// 超出内存预算的帧顺序写入匿名临时文件（位于 TMPDIR）。`finish` 时各帧成为块内视图，
+/// 落盘部分整体 mmap，由操作系统按需换入换出
+pub struct FrameStore {
+    budget: usize,
+    resident: usize,
+    blocks: Vec<Vec<u8>>,
+    spill: Option<BufWriter<File>>,
+    spill_len: usize,
+    /// 帧元数据，像素在 finish 时按 slots 填入
+    frames: Vec<PyVideoFrame>,
+    slots: Vec<Slot>,
+}
+
+impl FrameStore {
+    /// `budget` 为常驻内存的像素字节上限，None 表示不限（全部留在内存）
+    pub fn new(budget: Option<usize>) -> Self {
+        Self { budget: budget.unwrap_or(usize::MAX), resident: 0, blocks: Vec::new(), spill: None, spill_len: 0, frames: Vec::new(), slots: Vec::new() }
+    }
+
+    /// 已位于共享块（另一个 FrameStore 或 mmap）中的帧只增加引用，不复制也不计入本预算
+    pub fn push(&mut self, frame: &PyVideoFrame) -> Result<()> {
+        let pixels = frame.pixels(); let n = pixels.len();
+        let slot = if let FrameData::Shared { .. } = frame.data {
+            Slot::Shared(frame.data.clone())
+        } else if self.resident.saturating_add(n) <= self.budget {
+            if self.blocks.last().is_none_or(|b| b.capacity() - b.len() < n) {
+                // 块大小不超过剩余预算，避免小预算时一次性申请整块
+                self.blocks.push(Vec::with_capacity(ARENA_BLOCK.min(self.budget - self.resident).max(n)));
+            }
+            let index = self.blocks.len() - 1; let block = &mut self.blocks[index];
+            let slot = Slot::Heap(index, block.len(), n); block.extend_from_slice(pixels); self.resident += n;
+            slot
+        } else {
+            if self.spill.is_none() { self.spill = Some(BufWriter::with_capacity(1024*1024, tempfile::tempfile().context("Failed to create frame spill file")?)); }
+            self.spill.as_mut().expect("spill file opened").write_all(pixels).context("Failed to write frame spill file")?;
+            let slot = Slot::Spill(self.spill_len, n); self.spill_len += n;
+            slot
+        };
+        self.frames.push(PyVideoFrame::from_data(frame.frame_number, frame.width, frame.height, FrameData::Owned(Vec::new()), frame.timestamp));
+        self.slots.push(slot);
+        Ok(())
+    }
+
+    /// 落盘的字节数
+    pub fn spilled_bytes(&self) -> usize { self.spill_len }
+
+    pub fn finish(mut self) -> Result<Vec<PyVideoFrame>> {
+        let blocks: Vec<Arc<Block>> = self.blocks.into_iter().map(|b| Arc::new(Block::Heap(b))).collect();
+        let map = match self.spill.take() {
+            Some(spill) => {
+                let file = spill.into_inner().map_err(|e| e.into_error()).context("Failed to flush frame spill file")?;
+                // SAFETY: 匿名临时文件只有本进程持有，映射后不再写入或截断
+                Some(Arc::new(Block::Mapped(unsafe { Mmap::map(&file) }.context("Failed to map frame spill file")?)))
+            }
+            None => None,
+        };
+        for (frame, slot) in self.frames.iter_mut().zip(self.slots) {
+            frame.data = match slot {
+                Slot::Heap(b, offset, len) => FrameData::Shared { block: blocks[b].clone(), offset, len },
+                Slot::Spill(offset, len) => FrameData::Shared { block: map.clone().expect("spill file mapped"), offset, len },
+                Slot::Shared(data) => data,
+            };
+        }
+        Ok(self.frames)
+    }
+}
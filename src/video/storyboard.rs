@@ -0,0 +1,132 @@
+// 由 pyo3 为 #[pymethods] 生成的包装代码触发，原因见 lib.rs 的 functions 模块
+#![allow(clippy::useless_conversion)]
+
+use anyhow::Result;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{Rgb, RgbImage};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::path::{Path, PathBuf};
+
+use crate::video::crop::PyCropRect;
+use crate::video::encode::{decode_keyframe_images, encode_image, DecodeSize, EncodeParams};
+use crate::video::input::VideoInput;
+
+/// 关键帧拼成的故事板（单张网格图）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyStoryboard {
+    #[pyo3(get)] pub width: u32,
+    #[pyo3(get)] pub height: u32,
+    #[pyo3(get)] pub columns: usize,
+    #[pyo3(get)] pub rows: usize,
+    #[pyo3(get)] pub format: String,
+    /// 按格子顺序（行优先）排列的帧号与时间戳
+    #[pyo3(get)] pub frame_indices: Vec<usize>,
+    #[pyo3(get)] pub timestamps: Vec<f64>,
+    /// 每个格子在图中的 (x, y, width, height)，可据此把模型输出的位置映射回帧
+    #[pyo3(get)] pub tiles: Vec<(u32, u32, u32, u32)>,
+    pub data: Vec<u8>,
+}
+
+#[pymethods]
+impl PyStoryboard {
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> { PyBytes::new_bound(py, &self.data) }
+    fn data_uri(&self) -> String { format!("data:image/{};base64,{}", self.format, base64::engine::general_purpose::STANDARD.encode(&self.data)) }
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        std::fs::write(&path, &self.data).map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError,_>(format!("Failed to write {}: {}", path.display(), e)))
+    }
+    fn __repr__(&self) -> String { format!("PyStoryboard({}x{}, {} tiles in {}x{}, format='{}', {} bytes)", self.width, self.height, self.frame_indices.len(), self.columns, self.rows, self.format, self.data.len()) }
+}
+
+#[derive(Debug, Clone)]
+pub struct StoryboardParams {
+    /// 0 表示自动（接近正方形的列数）
+    pub columns: usize,
+    pub tile_width: u32,
+    /// 0 表示按首帧宽高比推算
+    pub tile_height: u32,
+    pub padding: u32,
+    /// 在每格左下角绘制时间码
+    pub timecodes: bool,
+    pub background: [u8; 3],
+    pub encode: EncodeParams,
+}
+
+impl Default for StoryboardParams {
+    fn default() -> Self { Self { columns: 0, tile_width: 320, tile_height: 0, padding: 4, timecodes: true, background: [0, 0, 0], encode: EncodeParams::default() } }
+}
+
+/// 3x5 点阵字体，每行低 3 位有效（高位在左）；仅覆盖时间码用到的字符
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111], '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111], '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001], '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111], '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111], '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000], '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => [0; 5],
+    }
+}
+
+/// MM:SS.mmm，超过一小时为 H:MM:SS.mmm
+pub fn timecode(secs: f64) -> String {
+    let ms = (secs.max(0.0) * 1000.0).round() as u64;
+    let (h, m, s, ms) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+    if h > 0 { format!("{}:{:02}:{:02}.{:03}", h, m, s, ms) } else { format!("{:02}:{:02}.{:03}", m, s, ms) }
+}
+
+/// 在 (x, y) 处绘制带半透明黑底的白色文字，超出画布的部分裁掉
+fn draw_caption(img: &mut RgbImage, x: u32, y: u32, text: &str, scale: u32) {
+    let (w, h) = ((text.chars().count() as u32 * 4 + 1) * scale, 7 * scale);
+    for py in y..(y + h).min(img.height()) { for px in x..(x + w).min(img.width()) { let p = img.get_pixel_mut(px, py); p.0 = p.0.map(|c| (c as u32 * 2 / 5) as u8); } }
+    for (i, c) in text.chars().enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() { for col in 0..3 {
+            if bits >> (2 - col) & 1 == 0 { continue; }
+            let (gx, gy) = (x + (1 + i as u32 * 4 + col) * scale, y + (1 + row as u32) * scale);
+            for py in gy..(gy + scale).min(img.height()) { for px in gx..(gx + scale).min(img.width()) { img.put_pixel(px, py, Rgb([255, 255, 255])); } }
+        } }
+    }
+}
+
+/// 等比缩放到格子内并居中（信箱留白为背景色）
+fn fit_tile(rgb: &RgbImage, tw: u32, th: u32, background: [u8; 3]) -> RgbImage {
+    let scale = (tw as f64 / rgb.width() as f64).min(th as f64 / rgb.height() as f64);
+    let (w, h) = (((rgb.width() as f64 * scale).round() as u32).clamp(1, tw), ((rgb.height() as f64 * scale).round() as u32).clamp(1, th));
+    let mut tile = RgbImage::from_pixel(tw, th, Rgb(background));
+    image::imageops::replace(&mut tile, &image::imageops::resize(rgb, w, h, FilterType::Triangle), ((tw - w) / 2) as i64, ((th - h) / 2) as i64);
+    tile
+}
+
+/// 解码在调用线程上进行，只有逐格缩放在 `pool` 中并行
+pub fn build_storyboard(input: &VideoInput, ffmpeg_path: &Path, indices: &[usize], crop: Option<&PyCropRect>, params: &StoryboardParams, pool: &ThreadPool) -> Result<PyStoryboard> {
+    let decoded = decode_keyframe_images(input, ffmpeg_path, indices, crop, DecodeSize::Native)?;
+    if decoded.is_empty() { anyhow::bail!("No keyframes decoded for storyboard"); }
+    let n = decoded.len();
+    let columns = if params.columns == 0 { (n as f64).sqrt().ceil() as usize } else { params.columns.min(n) };
+    let rows = n.div_ceil(columns);
+    let tw = params.tile_width.max(1);
+    let th = if params.tile_height > 0 { params.tile_height } else { let first = &decoded[0].2; ((tw as f64 * first.height() as f64 / first.width() as f64).round() as u32).max(1) };
+    let pad = params.padding;
+    let (width, height) = (columns as u32 * tw + (columns as u32 + 1) * pad, rows as u32 * th + (rows as u32 + 1) * pad);
+    let scale = (tw / 160).max(1);
+    let tiles: Vec<RgbImage> = pool.install(|| decoded.par_iter().map(|(_, t, rgb)| {
+        let mut tile = fit_tile(rgb, tw, th, params.background);
+        if params.timecodes { draw_caption(&mut tile, 0, th.saturating_sub(7 * scale), &timecode(*t), scale); }
+        tile
+    }).collect());
+    let mut sheet = RgbImage::from_pixel(width, height, Rgb(params.background));
+    let mut rects = Vec::with_capacity(n);
+    for (k, tile) in tiles.iter().enumerate() {
+        let (x, y) = (pad + (k % columns) as u32 * (tw + pad), pad + (k / columns) as u32 * (th + pad));
+        image::imageops::replace(&mut sheet, tile, x as i64, y as i64); rects.push((x, y, tw, th));
+    }
+    let encode = EncodeParams { max_dimension: 0, max_bytes: 0, ..params.encode.clone() };
+    let (data, _, _) = encode_image(sheet, &encode)?;
+    Ok(PyStoryboard { width, height, columns, rows, format: encode.format.name().into(), frame_indices: decoded.iter().map(|d| d.0).collect(), timestamps: decoded.iter().map(|d| d.1).collect(), tiles: rects, data })
+}
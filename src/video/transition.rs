@@ -0,0 +1,146 @@
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::video::frame::PyVideoFrame;
+use crate::video::mask::PyFrameMask;
+use crate::video::utils::{compute_frame_diffs, diff_curve};
+
+/// 渐变 / 空白帧检测结果（帧区间为闭区间）
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct PyTransitionSegment {
+    /// fade_in / fade_out / dissolve / black / white / blank
+    #[pyo3(get)] pub kind: String,
+    #[pyo3(get)] pub start_frame: usize,
+    #[pyo3(get)] pub end_frame: usize,
+}
+
+#[pymethods]
+impl PyTransitionSegment {
+    #[getter]
+    fn length(&self) -> usize { self.end_frame - self.start_frame + 1 }
+    fn __repr__(&self) -> String { format!("PyTransitionSegment(kind='{}', start_frame={}, end_frame={})", self.kind, self.start_frame, self.end_frame) }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransitionParams {
+    /// 均值 <= 该值且方差足够低 -> 黑帧
+    pub black_threshold: f64,
+    /// 均值 >= 该值且方差足够低 -> 白帧
+    pub white_threshold: f64,
+    /// 方差 <= 该值视为“空白”（纯色）帧
+    pub blank_variance: f64,
+    pub min_fade_frames: usize,
+    pub min_dissolve_frames: usize,
+}
+
+impl Default for TransitionParams {
+    fn default() -> Self { Self { black_threshold: 32.0, white_threshold: 223.0, blank_variance: 36.0, min_fade_frames: 3, min_dissolve_frames: 4 } }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlankKind { Black, White, Blank }
+
+impl BlankKind {
+    pub fn as_str(&self) -> &'static str { match self { BlankKind::Black => "black", BlankKind::White => "white", BlankKind::Blank => "blank" } }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LumaStats { pub mean: f64, pub variance: f64 }
+
+/// 单次遍历计算亮度均值与方差；传入掩码时只统计掩码内像素
+pub fn luma_stats(frame: &PyVideoFrame, mask: Option<&PyFrameMask>) -> LumaStats {
+    let pixels = frame.pixels();
+    let acc = |(s, sq, n): (u64, u64, u64), p: u8| (s + p as u64, sq + (p as u64) * (p as u64), n + 1);
+    let (sum, sum_sq, count) = match mask {
+        Some(m) => pixels.iter().zip(&m.data).filter(|(_, k)| **k != 0).fold((0, 0, 0), |a, (&p, _)| acc(a, p)),
+        None => pixels.iter().fold((0, 0, 0), |a, &p| acc(a, p)),
+    };
+    if count == 0 { return LumaStats::default(); }
+    let n = count as f64; let mean = sum as f64 / n;
+    LumaStats { mean, variance: (sum_sq as f64 / n - mean * mean).max(0.0) }
+}
+
+pub fn compute_luma_stats(frames: &[PyVideoFrame], mask: Option<&PyFrameMask>) -> Vec<LumaStats> { frames.par_iter().map(|f| luma_stats(f, mask)).collect() }
+
+pub fn classify_blank(s: &LumaStats, params: &TransitionParams) -> Option<BlankKind> {
+    if s.variance > params.blank_variance { return None; }
+    if s.mean <= params.black_threshold { Some(BlankKind::Black) } else if s.mean >= params.white_threshold { Some(BlankKind::White) } else { Some(BlankKind::Blank) }
+}
+
+/// 基于亮度均值/方差趋势检测淡入、淡出、叠化以及黑/白/空白帧。
+/// `diffs[i]` 为第 i-1 帧与第 i 帧的平均像素差（diffs[0] 忽略）。
+pub fn detect_transitions(stats: &[LumaStats], diffs: &[f64], params: &TransitionParams) -> Vec<PyTransitionSegment> {
+    let n = stats.len(); let mut segments = Vec::new();
+    if n == 0 { return segments; }
+    let blank: Vec<Option<BlankKind>> = stats.iter().map(|s| classify_blank(s, params)).collect();
+    // 1. 连续空白帧
+    let mut i = 0;
+    while i < n {
+        if let Some(kind) = blank[i] {
+            let start = i; while i + 1 < n && blank[i + 1] == Some(kind) { i += 1; }
+            segments.push(PyTransitionSegment { kind: kind.as_str().into(), start_frame: start, end_frame: i });
+        }
+        i += 1;
+    }
+    // 2. 淡出（空白段之前）/ 淡入（空白段之后）：方差单调变化，均值朝空白亮度单调靠近
+    let blanks: Vec<(usize, usize)> = segments.iter().map(|s| (s.start_frame, s.end_frame)).collect();
+    // 每一步都必须在均值或方差上有实际推进，静止画面不算入渐变
+    let toward = |from: &LumaStats, to: &LumaStats, target: f64| {
+        let (d_from, d_to) = ((from.mean - target).abs(), (to.mean - target).abs());
+        d_to <= d_from + 1.0 && to.variance <= from.variance * 1.05 + 1.0 && (d_from - d_to > 0.5 || from.variance - to.variance > from.variance * 0.02)
+    };
+    for &(start, end) in &blanks {
+        let target = stats[start].mean;
+        let mut s = start;
+        while s > 0 && blank[s - 1].is_none() && toward(&stats[s - 1], &stats[s], target) { s -= 1; }
+        if start - s >= params.min_fade_frames && stats[s].variance > params.blank_variance * 2.0 {
+            segments.push(PyTransitionSegment { kind: "fade_out".into(), start_frame: s, end_frame: start - 1 });
+        }
+        let target = stats[end].mean;
+        let mut e = end;
+        while e + 1 < n && blank[e + 1].is_none() && toward(&stats[e + 1], &stats[e], target) { e += 1; }
+        if e - end >= params.min_fade_frames && stats[e].variance > params.blank_variance * 2.0 {
+            segments.push(PyTransitionSegment { kind: "fade_in".into(), start_frame: end + 1, end_frame: e });
+        }
+    }
+    // 3. 叠化：连续的小而稳定的帧差，且中间帧方差低于两端（两画面混合会压低方差）
+    let covered = |idx: usize, segs: &[PyTransitionSegment]| segs.iter().any(|s| idx >= s.start_frame && idx <= s.end_frame);
+    let (min_step, max_step) = (0.5, 20.0);
+    let mut i = 1;
+    while i < n.min(diffs.len()) {
+        if diffs[i] < min_step || diffs[i] > max_step || covered(i, &segments) { i += 1; continue; }
+        let run_start = i; let mut run_end = i;
+        while run_end + 1 < n.min(diffs.len()) && diffs[run_end + 1] >= min_step && diffs[run_end + 1] <= max_step && !covered(run_end + 1, &segments) { run_end += 1; }
+        let (a, b) = (run_start - 1, run_end);
+        if b - a >= params.min_dissolve_frames {
+            let run = &diffs[run_start..=run_end];
+            let mean_step = run.iter().sum::<f64>() / run.len() as f64;
+            let steady = run.iter().all(|d| *d <= mean_step * 2.5);
+            let dip = stats[a + 1..b].iter().map(|s| s.variance).fold(f64::MAX, f64::min);
+            if steady && dip < stats[a].variance.min(stats[b].variance) * 0.8 {
+                segments.push(PyTransitionSegment { kind: "dissolve".into(), start_frame: a, end_frame: b });
+            }
+        }
+        i = run_end + 1;
+    }
+    segments.sort_by_key(|s| (s.start_frame, s.end_frame));
+    segments
+}
+
+/// 便捷入口：直接从帧序列检测过渡段
+pub fn detect_transitions_in_frames(frames: &[PyVideoFrame], use_simd: bool, block_size: usize, params: &TransitionParams) -> Vec<PyTransitionSegment> {
+    let stats = compute_luma_stats(frames, None);
+    let curve = diff_curve(&compute_frame_diffs(frames, use_simd, block_size, None), frames.len());
+    detect_transitions(&stats, &curve, params)
+}
+
+/// 把落在过渡段内的帧号移动到过渡结束后的第一帧（连续过渡链会一直向后推进）
+pub fn snap_to_transition_end(idx: usize, segments: &[PyTransitionSegment], total_frames: usize) -> usize {
+    let mut cur = idx;
+    while let Some(seg) = segments.iter().find(|s| cur >= s.start_frame && cur <= s.end_frame) {
+        if seg.end_frame + 1 >= total_frames { return total_frames - 1; }
+        cur = seg.end_frame + 1;
+    }
+    cur
+}
@@ -1,79 +1,422 @@
 use anyhow::{Context, Result};
-use std::io::{BufReader, Read};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::Instant;
 use chrono::prelude::*;
 use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::sync::Arc;
 
 // 简洁可控输出
 macro_rules! vprintln { ($v:expr, $($t:tt)*) => { if $v { println!($($t)*); } } }
 
-use crate::video::frame::PyVideoFrame;
+use crate::video::animation::{decode_rgb_frames, AnimationFormat};
+use crate::video::crop::PyCropRect;
+use crate::video::diversity::{select_diverse, DiversityParams};
+use crate::video::encode::{encode_image, EncodeParams};
+use crate::video::frame::{PyVideoFrame, DEFAULT_FPS};
+use crate::video::frozen::{collapse_frozen, detect_frozen_segments, FrozenParams};
+use crate::video::loops::{detect_loop, LoopParams};
+use crate::video::input::VideoInput;
+use crate::video::mask::PyFrameMask;
+use crate::video::naming::{NameContext, SaveOptions};
+use crate::video::slides::{select_slides, SlideParams};
+use crate::video::source::{open_source, FrameSource};
 use crate::video::performance::PerformanceResult;
+use crate::video::quality::refine_by_quality;
+use crate::video::stats::{compute_frame_stats, FrameStats, PyFrameStats};
+use crate::video::store::FrameStore;
+use crate::video::transition::{classify_blank, compute_luma_stats, detect_transitions, snap_to_transition_end, TransitionParams};
 
-pub fn extract_frames_memory_stream(video_path:&PathBuf, ffmpeg_path:&PathBuf, _deprecated_max_frames:usize, verbose:bool)->Result<(Vec<PyVideoFrame>,usize,usize)> {
+/// 探测得到的视频基本信息
+#[derive(Debug, Clone, Copy)]
+pub struct VideoInfo { pub width:usize, pub height:usize, pub fps:f64 }
+
+pub fn probe_video(input:&VideoInput, ffmpeg_path:&Path)->Result<VideoInfo> { parse_probe_info(&probe_text(input, ffmpeg_path)?) }
+
+/// `ffmpeg -i` 的探测输出（stderr 文本）
+pub fn probe_text(input:&VideoInput, ffmpeg_path:&Path)->Result<String> {
+    let mut cmd = Command::new(ffmpeg_path); input.add_ffmpeg_input(&mut cmd); cmd.arg("-hide_banner");
+    let probe_output = input.ffmpeg_output(&mut cmd).context("Failed to probe video with FFmpeg")?;
+    Ok(String::from_utf8_lossy(&probe_output.stderr).into_owned())
+}
+
+pub fn parse_probe_info(probe_info:&str)->Result<VideoInfo> {
+    let (width,height)=parse_video_dimensions(probe_info).ok_or_else(|| anyhow::anyhow!("Cannot parse video dimensions"))?;
+    Ok(VideoInfo { width, height, fps: parse_video_fps(probe_info).unwrap_or(DEFAULT_FPS) })
+}
+
+/// 探测可供 ffmpeg 读取的输入：管道无法随机访问，探测失败（如 moov 在末尾的 MP4）时退回临时文件。
+/// 返回实际使用的输入与需要保持存活的临时文件
+pub fn probe_seekable(input:&VideoInput, ffmpeg_path:&Path)->Result<(VideoInfo,VideoInput,Option<tempfile::NamedTempFile>)> {
+    match probe_video(input, ffmpeg_path) {
+        Ok(info) => Ok((info, input.clone(), None)),
+        Err(e) if matches!(input, VideoInput::Bytes(_)) => {
+            let (path, tmp) = input.as_file()?; let spilled = VideoInput::Path(path);
+            let info = probe_video(&spilled, ffmpeg_path).context(e)?;
+            Ok((info, spilled, tmp))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 流式解码灰度帧：每解出一帧回调一次，回调返回 false 时提前结束。返回 (视频信息, 帧数)
+/// 按输入内容自动选择帧来源（见 `open_source`）；来源内部的并行解码在 `pool` 中执行
+pub fn stream_frames<F:FnMut(&PyVideoFrame)->bool>(input:&VideoInput, ffmpeg_path:&Path, workers:usize, pool:&Arc<ThreadPool>, verbose:bool, on_frame:F)->Result<(VideoInfo,usize)> {
+    let mut source = open_source(input, ffmpeg_path, workers); source.use_thread_pool(pool);
+    stream_source(source.as_mut(), verbose, on_frame)
+}
+
+pub fn stream_source<F:FnMut(&PyVideoFrame)->bool>(source:&mut dyn FrameSource, verbose:bool, mut on_frame:F)->Result<(VideoInfo,usize)> {
+    vprintln!(verbose, "Decoding {}", source.describe());
+    let start = Instant::now(); let mut seen = 0usize;
+    let (info,frame_count) = source.stream(&mut |f| { seen+=1; if verbose && seen.is_multiple_of(1000) { vprintln!(true, "Processed {} frames", seen); } on_frame(f) })?;
+    vprintln!(verbose, "Dimensions: {}x{} @ {:.3} fps", info.width,info.height,info.fps);
+    vprintln!(verbose, "Done: {} frames in {:.2}s", frame_count, start.elapsed().as_secs_f64());
+    Ok((info,frame_count))
+}
+
+/// `workers` 为 ffmpeg 并发解码进程数（1 为单进程）；`ram_budget` 见 `collect_frames`；来源内部的并行解码在 `pool` 中执行
+pub fn extract_frames_memory_stream(input:&VideoInput, ffmpeg_path:&Path, _deprecated_max_frames:usize, workers:usize, ram_budget:Option<usize>, pool:&Arc<ThreadPool>, verbose:bool)->Result<(Vec<PyVideoFrame>,usize,usize)> {
     // _deprecated_max_frames 参数保留仅为兼容内部调用；实际总是提取全部帧
-    vprintln!(verbose, "Extracting frames (full video): {}", video_path.display());
-    let probe_output = Command::new(ffmpeg_path).args(["-i", video_path.to_str().unwrap(), "-hide_banner"]).output().context("Failed to probe video with FFmpeg")?;
-    let probe_info = String::from_utf8_lossy(&probe_output.stderr);
-    let (width,height)=parse_video_dimensions(&probe_info).ok_or_else(|| anyhow::anyhow!("Cannot parse video dimensions"))?;
-    vprintln!(verbose, "Dimensions: {}x{}", width,height);
-    let mut cmd = Command::new(ffmpeg_path); cmd.args(["-i", video_path.to_str().unwrap(), "-f","rawvideo","-pix_fmt","gray","-an","-threads","0","-preset","ultrafast"]);
-    // 不再限制帧数：忽略 max_frames
-    cmd.args(["-"]).stdout(Stdio::piped()).stderr(Stdio::null());
-    let start = Instant::now(); let mut child = cmd.spawn().context("Failed to spawn FFmpeg process")?; let stdout = child.stdout.take().unwrap();
-    let mut reader = BufReader::with_capacity(1024*1024, stdout); let frame_size = width*height; let mut frames=Vec::new(); let mut frame_count=0; let mut buf=vec![0u8;frame_size];
-    vprintln!(verbose, "Frame size: {} bytes", frame_size);
-    loop { match reader.read_exact(&mut buf) { Ok(()) => { frames.push(PyVideoFrame::new(frame_count,width,height,buf.clone())); frame_count+=1; if verbose && frame_count%1000==0 { vprintln!(true, "Processed {} frames", frame_count); } }, Err(_) => break } }
-    let _ = child.wait(); vprintln!(verbose, "Done: {} frames in {:.2}s", frame_count, start.elapsed().as_secs_f64());
-    Ok((frames,width,height))
+    vprintln!(verbose, "Extracting frames (full video): {}", input.display_name());
+    let mut source = open_source(input, ffmpeg_path, workers); source.use_thread_pool(pool);
+    collect_frames(source.as_mut(), ram_budget, verbose)
+}
+
+/// 收集全部帧；超过 `ram_budget`（像素字节数，None 不限）的帧落盘到 mmap 临时文件
+pub fn collect_frames(source:&mut dyn FrameSource, ram_budget:Option<usize>, verbose:bool)->Result<(Vec<PyVideoFrame>,usize,usize)> {
+    let mut store = FrameStore::new(ram_budget); let mut err = None; source.use_ram_budget(ram_budget);
+    let (info,_) = stream_source(source, verbose, |f| match store.push(f) { Ok(()) => true, Err(e) => { err = Some(e); false } })?;
+    if let Some(e) = err { return Err(e); }
+    if store.spilled_bytes() > 0 { vprintln!(verbose, "Spilled {:.1} MB of frames to disk", store.spilled_bytes() as f64 / 1048576.0); }
+    Ok((store.finish()?,info.width,info.height))
+}
+
+/// 边解码边统计，不保留帧数据；按批在 `pool` 中并行计算以摊薄单帧开销（来源本身在调用线程上解码）
+pub fn extract_frame_stats_stream(source:&mut dyn FrameSource, edge_threshold:u32, pool:&rayon::ThreadPool, verbose:bool)->Result<PyFrameStats> {
+    const BATCH: usize = 64;
+    // 批内的帧缓冲在各批之间复用
+    let mut out = PyFrameStats::default(); let mut batch: Vec<PyVideoFrame> = Vec::with_capacity(BATCH); let mut len = 0;
+    let flush = |batch: &[PyVideoFrame], out: &mut PyFrameStats| {
+        let stats: Vec<FrameStats> = pool.install(|| batch.par_iter().map(|f| compute_frame_stats(f, edge_threshold)).collect());
+        for (f, s) in batch.iter().zip(stats) { out.push(f, s); }
+    };
+    stream_source(source, verbose, |f| {
+        if len < batch.len() { batch[len].copy_from(f); } else { batch.push(f.clone()); }
+        len += 1; if len == BATCH { flush(&batch, &mut out); len = 0; } true
+    })?;
+    flush(&batch[..len], &mut out);
+    Ok(out)
 }
 
 pub fn parse_video_dimensions(info:&str)->Option<(usize,usize)> { for line in info.lines() { if line.contains("Video:") && line.contains('x') { for part in line.split_whitespace() { if let Some(p)=part.find('x') { let (w,h_part)=part.split_at(p); let h_seg=&h_part[1..]; let h_str=h_seg.split(',').next().unwrap_or(h_seg); if let (Ok(wu),Ok(hu))=(w.parse(), h_str.parse()) { return Some((wu,hu)); } } } } } None }
 
+/// 解析 "Duration: HH:MM:SS.xx"（秒）；流式输入等为 N/A 时返回 None
+pub fn parse_video_duration(info:&str)->Option<f64> {
+    let v = info.lines().find_map(|l| l.trim().strip_prefix("Duration:"))?.split(',').next()?.trim();
+    let mut parts = v.split(':').map(|p| p.parse::<f64>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(h * 3600.0 + m * 60.0 + s).filter(|d| *d > 0.0)
+}
+
+/// 从 ffmpeg 探测输出中解析帧率：优先 "xx fps"，其次 "xx tbr"
+pub fn parse_video_fps(info:&str)->Option<f64> {
+    let line = info.lines().find(|l| l.contains("Video:"))?;
+    let parts: Vec<&str> = line.split(',').map(|p| p.trim()).collect();
+    ["fps", "tbr"].iter().find_map(|unit| parts.iter().find_map(|p| {
+        let v = p.strip_suffix(unit)?.trim();
+        let fps = if let Some(k) = v.strip_suffix('k') { k.parse::<f64>().ok()? * 1000.0 } else { v.parse::<f64>().ok()? };
+        if fps > 0.0 { Some(fps) } else { None }
+    }))
+}
+
+/// 关键帧挑选策略
+#[derive(Debug, Clone, Default)]
+pub enum SelectionStrategy {
+    /// 帧差最大的 K 帧（原有行为）
+    #[default]
+    TopK,
+    /// 帧差 >= 阈值的帧（超过 K 个时保留差异最大的 K 个）
+    Threshold(f64),
+    /// 按时间均匀分布
+    Uniform,
+    /// 将视频平均切成 K 段，每段取帧差最大的一帧
+    SegmentQuota,
+    /// 按视觉特征聚类后取互不相似的代表帧
+    Diverse(DiversityParams),
+    /// 幻灯片/录屏：每页取最后一个静止帧
+    Slides(SlideParams),
+}
+
+impl SelectionStrategy {
+    pub const DEFAULT_THRESHOLD: f64 = 30.0;
+    pub fn parse(name:&str, threshold:Option<f64>, diversity:DiversityParams)->Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "top_k" | "topk" => Some(Self::TopK),
+            "threshold" => Some(Self::Threshold(threshold.unwrap_or(Self::DEFAULT_THRESHOLD))),
+            "uniform" => Some(Self::Uniform),
+            "segment_quota" => Some(Self::SegmentQuota),
+            "diverse" => Some(Self::Diverse(diversity)),
+            "slides" | "slide" => Some(Self::Slides(SlideParams::default())),
+            _ => None,
+        }
+    }
+}
+
+/// 关键帧筛选附加选项；默认值与原有 Top-K 行为一致
+#[derive(Debug, Clone, Default)]
+pub struct SelectionOptions {
+    pub strategy: SelectionStrategy,
+    /// 不选择黑/白/纯色帧
+    pub exclude_blank: bool,
+    /// 落在淡入淡出/叠化等过渡段内的关键帧移动到过渡结束处
+    pub snap_to_transition_end: bool,
+    pub transition: TransitionParams,
+    /// 变化点之后向后搜索画质最佳帧的窗口（帧数，0 表示关闭）
+    pub refine_window: usize,
+    /// 帧差与亮度统计只在掩码区域内计算
+    pub mask: Option<PyFrameMask>,
+    /// 去黑边后的有效画面；分析时与 mask 取交集
+    pub crop: Option<PyCropRect>,
+    /// 同一静止段内只保留一个关键帧
+    pub collapse_frozen: Option<FrozenParams>,
+    /// 循环片段只在第一个周期内选帧
+    pub single_cycle: Option<LoopParams>,
+}
+
+impl SelectionOptions {
+    fn needs_luma_stats(&self) -> bool { self.exclude_blank || self.snap_to_transition_end }
+    /// 掩码与裁剪区域合并后的分析区域
+    fn analysis_mask(&self, width:usize, height:usize) -> Option<PyFrameMask> {
+        match (&self.mask, &self.crop) {
+            (m, None) => m.clone(),
+            (None, Some(c)) => Some(c.to_mask(width, height)),
+            (Some(m), Some(c)) => Some(m.intersect(&c.to_mask(width, height))),
+        }
+    }
+}
+
+/// 相邻帧差异：返回 (后一帧索引, 平均像素差 0..=255)
+/// 传入 `mask` 时只统计掩码内像素
+pub fn compute_frame_diffs(frames:&[PyVideoFrame], use_simd:bool, block_size:usize, mask:Option<&PyFrameMask>)->Vec<(usize,f64)> {
+    frames.par_windows(2).enumerate().map(|(i,p)| {
+        let raw = match mask {
+            Some(m) => p[0].calculate_difference_masked(&p[1], m, block_size, use_simd),
+            None if use_simd => p[0].calculate_difference_parallel_simd(&p[1], block_size, true),
+            None => p[0].calculate_difference(&p[1], None).unwrap_or(f64::MAX),
+        };
+        (i+1, raw)
+    }).collect()
+}
+
+/// 按帧号展开的差异曲线，curve[0] = 0
+pub fn diff_curve(diffs:&[(usize,f64)], total:usize)->Vec<f64> { let mut curve = vec![0.0; total]; for &(i,d) in diffs { curve[i] = d; } curve }
+
 pub fn extract_keyframes_optimized(frames:&[PyVideoFrame], max_keyframes:usize, use_simd:bool, block_size:usize, verbose:bool)->Result<Vec<usize>> {
+    extract_keyframes_with_options(frames, max_keyframes, use_simd, block_size, &SelectionOptions::default(), verbose)
+}
+
+pub fn extract_keyframes_with_options(frames:&[PyVideoFrame], max_keyframes:usize, use_simd:bool, block_size:usize, opts:&SelectionOptions, verbose:bool)->Result<Vec<usize>> {
     if frames.len()<2 || max_keyframes==0 { return Ok(vec![]); }
     let opt_name = if use_simd { "SIMD+Parallel" } else { "Parallel" }; vprintln!(verbose, "Keyframe analysis target: {} ({})", max_keyframes, opt_name);
     let start = Instant::now();
     // 计算差异并归一化到 [0,1]
     // 收集原始差异 (平均像素差 0..=255)
-    let mut diffs: Vec<(usize,f64)> = frames.par_windows(2).enumerate().map(|(i,p)| {
-        let raw = if use_simd { p[0].calculate_difference_parallel_simd(&p[1], block_size, true) } else { p[0].calculate_difference(&p[1]).unwrap_or(f64::MAX) };
-        (i+1, raw)
-    }).collect();
-    let total_pairs = diffs.len();
-    if max_keyframes >= total_pairs { // 全部作为关键帧
-        let mut all: Vec<usize> = diffs.into_iter().map(|(i,_)| i).collect();
-        all.sort_unstable();
-        vprintln!(verbose, "Keyframes selected: {} (all) in {:.2}s", all.len(), start.elapsed().as_secs_f64());
-        return Ok(all);
+    if let Some(m) = &opts.mask { m.check(&frames[0])?; }
+    let mask = opts.analysis_mask(frames[0].width, frames[0].height);
+    let mut diffs = compute_frame_diffs(frames, use_simd, block_size, mask.as_ref());
+    let stats = if opts.needs_luma_stats() { compute_luma_stats(frames, mask.as_ref()) } else { Vec::new() };
+    let segments = if opts.snap_to_transition_end { detect_transitions(&stats, &diff_curve(&diffs, frames.len()), &opts.transition) } else { Vec::new() };
+    if opts.exclude_blank { diffs.retain(|(i,_)| classify_blank(&stats[*i], &opts.transition).is_none()); }
+    if let Some(info) = opts.single_cycle.as_ref().and_then(|p| detect_loop(frames, p)) {
+        diffs.retain(|(i,_)| *i < info.end_frame());
+        vprintln!(verbose, "Loop detected: start {} period {} ({:.2} cycles), selecting within first cycle", info.start_frame, info.period, info.cycles);
+    }
+    let mut selected = select_by_strategy(frames, diffs, max_keyframes, &opts.strategy, mask.as_ref())?;
+    vprintln!(verbose, "Keyframes selected: {} (requested {}, strategy {:?}) in {:.2}s", selected.len(), max_keyframes, opts.strategy, start.elapsed().as_secs_f64());
+    if opts.snap_to_transition_end {
+        selected = selected.into_iter().map(|i| snap_to_transition_end(i, &segments, frames.len())).collect();
+        if opts.exclude_blank { selected.retain(|i| classify_blank(&stats[*i], &opts.transition).is_none()); }
+        selected.sort_unstable(); selected.dedup();
+        vprintln!(verbose, "Snapped to transition ends: {} keyframes ({} transition segments)", selected.len(), segments.len());
     }
-    // 第 K 大差异 (K = max_keyframes)
-    let k_index = max_keyframes - 1;
+    if opts.refine_window > 0 {
+        selected = refine_by_quality(frames, &selected, opts.refine_window, |j| !opts.exclude_blank || classify_blank(&stats[j], &opts.transition).is_none());
+        vprintln!(verbose, "Refined by quality (window {}): {} keyframes", opts.refine_window, selected.len());
+    }
+    if let Some(params) = &opts.collapse_frozen {
+        let segments = detect_frozen_segments(frames, params, use_simd, block_size, mask.as_ref());
+        selected = collapse_frozen(&selected, &segments);
+        vprintln!(verbose, "Collapsed frozen segments: {} keyframes ({} frozen segments)", selected.len(), segments.len());
+    }
+    Ok(selected)
+}
+
+/// 第 K 大差异作为阈值，返回 >= 该值的所有索引（可能因并列多于 K 个）
+fn top_k_indices(mut diffs:Vec<(usize,f64)>, k:usize)->Vec<usize> {
+    if k >= diffs.len() { return diffs.into_iter().map(|(i,_)| i).collect(); } // 全部作为关键帧
+    let k_index = k - 1;
     diffs.select_nth_unstable_by(k_index, |a,b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
     let pivot = diffs[k_index].1; // Kth largest raw diff
-    // 拿到 >= pivot 的所有索引（可能多于 max_keyframes 因 ties）
-    let mut selected: Vec<usize> = diffs.into_iter().filter_map(|(idx,val)| if val >= pivot { Some(idx) } else { None }).collect();
-    selected.sort_unstable();
-    vprintln!(verbose, "Keyframes selected: {} (requested {}, pivot_diff={:.3}) in {:.2}s", selected.len(), max_keyframes, pivot, start.elapsed().as_secs_f64());
+    diffs.into_iter().filter_map(|(idx,val)| if val >= pivot { Some(idx) } else { None }).collect()
+}
+
+/// 按策略从候选 (帧号, 帧差) 中挑选关键帧；`diffs` 须按帧号升序
+pub fn select_by_strategy(frames:&[PyVideoFrame], diffs:Vec<(usize,f64)>, k:usize, strategy:&SelectionStrategy, mask:Option<&PyFrameMask>)->Result<Vec<usize>> {
+    if diffs.is_empty() || k == 0 { return Ok(vec![]); }
+    let total = frames.len();
+    let mut selected = match strategy {
+        SelectionStrategy::TopK => top_k_indices(diffs, k),
+        SelectionStrategy::Threshold(t) => top_k_indices(diffs.into_iter().filter(|(_,d)| d >= t).collect(), k),
+        SelectionStrategy::Uniform => { let k = k.min(diffs.len()); (0..k).map(|j| {
+            // 取离每个时间桶中心最近的候选帧；k 先截到候选数，桶才能铺满整段视频
+            let target = ((j as f64 + 0.5) * total as f64 / k as f64) as usize;
+            let pos = diffs.partition_point(|(i,_)| *i < target);
+            match (pos.checked_sub(1).map(|p| diffs[p].0), diffs.get(pos).map(|c| c.0)) {
+                (Some(a), Some(b)) => if target - a <= b - target { a } else { b },
+                (Some(a), None) => a, (None, Some(b)) => b, (None, None) => unreachable!(),
+            }
+        }).collect() }
+        SelectionStrategy::SegmentQuota => {
+            // 一次遍历：帧 i 落入第 i*k/total 段，每段保留帧差最大者（u128 避免乘法溢出）
+            let k = k.min(total);
+            let mut best: Vec<Option<(usize,f64)>> = vec![None; k];
+            for &(i, d) in &diffs {
+                let b = ((i as u128 * k as u128 / total as u128) as usize).min(k - 1);
+                if best[b].is_none_or(|x| d > x.1) { best[b] = Some((i, d)); }
+            }
+            best.into_iter().flatten().map(|c| c.0).collect()
+        }
+        SelectionStrategy::Diverse(params) => {
+            let pool = if params.candidates == 0 { k.saturating_mul(4) } else { params.candidates.max(k) };
+            let candidates = if pool < diffs.len() { let mut d = diffs; d.select_nth_unstable_by(pool - 1, |a,b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)); d.truncate(pool); d } else { diffs };
+            select_diverse(frames, &candidates, k, params.feature, params.method, params.min_distance)
+        }
+        // 只保留仍在候选中的帧（exclude_blank 已剔除的不再选回）
+        SelectionStrategy::Slides(params) => select_slides(frames, k, params, mask)?.into_iter().filter(|i| diffs.binary_search_by_key(i, |c| c.0).is_ok()).collect(),
+    };
+    selected.sort_unstable(); selected.dedup();
+    Ok(selected)
+}
+
+/// 多样性选帧：先按帧差取候选，再按紧凑特征聚类，返回至多 max_keyframes 个互不相似的代表帧
+pub fn extract_keyframes_diverse(frames:&[PyVideoFrame], max_keyframes:usize, use_simd:bool, block_size:usize, params:&DiversityParams, verbose:bool)->Result<Vec<usize>> {
+    if frames.len()<2 || max_keyframes==0 { return Ok(vec![]); }
+    let start = Instant::now();
+    let diffs = compute_frame_diffs(frames, use_simd, block_size, None);
+    let selected = select_by_strategy(frames, diffs, max_keyframes, &SelectionStrategy::Diverse(params.clone()), None)?;
+    vprintln!(verbose, "Diverse keyframes: {} ({:?}/{:?}) in {:.2}s", selected.len(), params.feature, params.method, start.elapsed().as_secs_f64());
     Ok(selected)
 }
 
-pub fn save_keyframes_optimized(video_path:&PathBuf, indices:&[usize], out_dir:&PathBuf, ffmpeg_path:&PathBuf, max_save:usize, verbose:bool)->Result<usize> {
-    use std::fs; if indices.is_empty(){ vprintln!(verbose, "No keyframes to save"); return Ok(0);} vprintln!(verbose, "Saving keyframes (max {})...", max_save);
-    fs::create_dir_all(out_dir).context("Failed to create output directory")?; let save_count=indices.len().min(max_save); let mut saved=0;
-    for (i,&idx) in indices.iter().take(save_count).enumerate() { let output_path = out_dir.join(format!("keyframe_{:03}.jpg", i+1)); let timestamp = idx as f64 / 30.0; let output = Command::new(ffmpeg_path).args(["-i", video_path.to_str().unwrap(), "-ss", &timestamp.to_string(), "-vframes","1","-q:v","2","-y", output_path.to_str().unwrap()]).output().context("Failed to extract keyframe with FFmpeg")?; if output.status.success() { saved+=1; } else if verbose { eprintln!("Save keyframe failed at frame {}", idx); } }
-    vprintln!(verbose, "Saved {}/{} keyframes", saved, save_count); Ok(saved)
+/// 按 `opts` 命名与编码保存关键帧；`scores` 与 `indices` 一一对应，仅用于 {score} 占位符。返回实际写出的数量
+#[allow(clippy::too_many_arguments)]
+pub fn save_keyframes_optimized(input:&VideoInput, indices:&[usize], scores:Option<&[f64]>, out_dir:&Path, ffmpeg_path:&Path, max_save:usize, crop:Option<&PyCropRect>, opts:&SaveOptions, verbose:bool)->Result<usize> {
+    if indices.is_empty(){ vprintln!(verbose, "No keyframes to save"); return Ok(0);} vprintln!(verbose, "Saving keyframes (max {})...", max_save);
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    let wanted: Vec<usize> = indices.iter().take(max_save).copied().collect(); let (stem, mut taken) = (input.stem(), HashSet::new());
+    let mut target = |i:usize, timestamp:f64| opts.target(out_dir, &NameContext { video_stem: &stem, index: i+1, total: wanted.len(), frame: wanted[i], timestamp, score: scores.and_then(|s| s.get(i).copied()) }, &mut taken);
+    if let Some(format) = AnimationFormat::sniff(&input.head(12)) {
+        // 动图在进程内解码与编码，无需启动 ffmpeg
+        let decoded = decode_rgb_frames(input, format, &wanted)?; let mut saved = 0;
+        let params = EncodeParams { format: opts.format, quality: opts.quality, max_dimension: opts.max_dimension, ..Default::default() };
+        for (i,idx) in wanted.iter().enumerate() {
+            let Some((_, timestamp, rgb)) = decoded.iter().find(|(j,_,_)| j==idx) else { continue }; let Some(dst) = target(i, *timestamp) else { continue };
+            let rgb = match crop { Some(c) => image::imageops::crop_imm(rgb, c.x as u32, c.y as u32, c.width as u32, c.height as u32).to_image(), None => rgb.clone() };
+            create_parent(&dst)?; fs::write(&dst, encode_image(rgb, &params)?.0).with_context(|| format!("Failed to write {}", dst.display()))?; saved+=1;
+        }
+        vprintln!(verbose, "Saved {}/{} keyframes", saved, wanted.len()); return Ok(saved);
+    }
+    let (info, input, _spilled) = probe_seekable(input, ffmpeg_path)?;
+    let targets: Vec<Option<PathBuf>> = (0..wanted.len()).map(|i| target(i, wanted[i] as f64 / info.fps)).collect();
+    let todo: Vec<usize> = (0..wanted.len()).filter(|&i| targets[i].is_some()).collect();
+    if todo.len() < wanted.len() { vprintln!(verbose, "Skipping {} keyframes whose output already exists", wanted.len()-todo.len()); }
+    if todo.is_empty() { return Ok(0); }
+    for dst in targets.iter().flatten() { create_parent(dst)?; }
+    let missing = match save_keyframes_select(&input, &wanted, &targets, out_dir, ffmpeg_path, crop, opts) {
+        Ok(missing) => missing,
+        Err(e) => { vprintln!(verbose, "Single-pass save failed ({}), falling back to per-frame seeking", e); todo.clone() }
+    };
+    // 兜底：逐帧以 -ss 置于 -i 之前快速定位；内存输入需先落盘才能随机访问
+    let mut saved = todo.len() - missing.len();
+    if !missing.is_empty() {
+        let (video_path, _tmp) = input.as_file()?;
+        for &i in &missing {
+            let Some(dst) = &targets[i] else { continue };
+            if save_keyframe_seek(&video_path, wanted[i] as f64 / info.fps, dst, ffmpeg_path, crop, opts)? { saved+=1; } else if verbose { eprintln!("Save keyframe failed at frame {}", wanted[i]); }
+        }
+    }
+    vprintln!(verbose, "Saved {}/{} keyframes", saved, wanted.len()); Ok(saved)
+}
+
+fn create_parent(path:&Path)->Result<()> { if let Some(dir) = path.parent() { fs::create_dir_all(dir).context("Failed to create output directory")?; } Ok(()) }
+
+/// 裁剪与缩放滤镜（按此顺序），均未设置时为 None
+fn output_filters(crop:Option<&PyCropRect>, opts:&SaveOptions)->Option<String> {
+    let filters: Vec<String> = crop.map(|c| c.to_ffmpeg_filter()).into_iter().chain(opts.scale_filter()).collect();
+    (!filters.is_empty()).then(|| filters.join(","))
 }
 
-pub fn run_performance_test(video_path:&PathBuf, max_keyframes:usize, test_name:&str, ffmpeg_path:&PathBuf, use_simd:bool, block_size:usize, verbose:bool)->Result<PerformanceResult> {
+/// 单次 ffmpeg 调用按帧号 select 导出全部待写关键帧，解出最后一个选中帧后即停止。返回未能写出的位置
+fn save_keyframes_select(input:&VideoInput, wanted:&[usize], targets:&[Option<PathBuf>], out_dir:&Path, ffmpeg_path:&Path, crop:Option<&PyCropRect>, opts:&SaveOptions)->Result<Vec<usize>> {
+    let mut unique: Vec<usize> = wanted.iter().zip(targets).filter(|(_, t)| t.is_some()).map(|(&i, _)| i).collect(); unique.sort_unstable(); unique.dedup();
+    let tmp = tempfile::Builder::new().prefix(".inkfox-").tempdir_in(out_dir).context("Failed to create temp directory")?;
+    let mut filter = format!("select='{}'", unique.iter().map(|i| format!("eq(n,{})", i)).collect::<Vec<_>>().join("+"));
+    if let Some(f) = output_filters(crop, opts) { filter.push(','); filter.push_str(&f); }
+    let mut cmd = Command::new(ffmpeg_path); cmd.args(["-hide_banner","-loglevel","error"]); input.add_ffmpeg_input(&mut cmd);
+    cmd.args(["-vf", &filter, "-vsync", "0", "-frames:v", &unique.len().to_string()]).args(opts.ffmpeg_args()).args(["-start_number", "0", "-y"]).arg(tmp.path().join(format!("%06d.{}", opts.format.extension())));
+    let output = input.ffmpeg_output(&mut cmd).context("Failed to extract keyframes with FFmpeg")?;
+    if !output.status.success() { anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim()); }
+    // 输出按帧号升序编号，再移动到各自的目标文件名
+    let (mut placed, mut missing) = (std::collections::HashMap::new(), Vec::new());
+    for (i, (&idx, dst)) in wanted.iter().zip(targets).enumerate() {
+        let Some(dst) = dst else { continue };
+        if let Some(prev) = placed.get(&idx) { fs::copy(prev, dst).context("Failed to copy keyframe")?; continue; }
+        let src = tmp.path().join(format!("{:06}.{}", unique.binary_search(&idx).unwrap_or_default(), opts.format.extension()));
+        if src.exists() { fs::rename(&src, dst).context("Failed to move keyframe")?; placed.insert(idx, dst.clone()); } else { missing.push(i); }
+    }
+    Ok(missing)
+}
+
+fn save_keyframe_seek(video_path:&Path, timestamp:f64, dst:&Path, ffmpeg_path:&Path, crop:Option<&PyCropRect>, opts:&SaveOptions)->Result<bool> {
+    let mut cmd = Command::new(ffmpeg_path); cmd.args(["-hide_banner","-loglevel","error","-ss",&format!("{:.6}", timestamp)]).arg("-i").arg(video_path);
+    if let Some(f) = output_filters(crop, opts) { cmd.args(["-vf", &f]); }
+    Ok(cmd.args(["-frames:v","1"]).args(opts.ffmpeg_args()).arg("-y").arg(dst).output().context("Failed to extract keyframe with FFmpeg")?.status.success())
+}
+
+/// 解码在调用线程上进行，只有关键帧分析在 `pool` 中执行
+#[allow(clippy::too_many_arguments)]
+pub fn run_performance_test(input:&VideoInput, max_keyframes:usize, test_name:&str, ffmpeg_path:&Path, use_simd:bool, block_size:usize, workers:usize, ram_budget:Option<usize>, pool:&Arc<ThreadPool>, verbose:bool)->Result<PerformanceResult> {
     vprintln!(verbose, "Run test: {} (max_keyframes={})", test_name, max_keyframes);
     let total_start = Instant::now(); let extraction_start = Instant::now();
-    let (frames,_,_) = extract_frames_memory_stream(video_path, ffmpeg_path, 0, verbose)?; let extraction_time = extraction_start.elapsed().as_secs_f64()*1000.0;
-    let analysis_start = Instant::now(); let keyframes = extract_keyframes_optimized(&frames, max_keyframes, use_simd, block_size, verbose)?; let analysis_time = analysis_start.elapsed().as_secs_f64()*1000.0;
+    let (frames,_,_) = extract_frames_memory_stream(input, ffmpeg_path, 0, workers, ram_budget, pool, verbose)?; let extraction_time = extraction_start.elapsed().as_secs_f64()*1000.0;
+    let analysis_start = Instant::now(); let keyframes = pool.install(|| extract_keyframes_optimized(&frames, max_keyframes, use_simd, block_size, verbose))?; let analysis_time = analysis_start.elapsed().as_secs_f64()*1000.0;
     let total_time = total_start.elapsed().as_secs_f64()*1000.0; let optimization_type = if use_simd { format!("SIMD+Parallel(block:{})", block_size) } else { "Standard Parallel".into() };
-    let result = PerformanceResult { test_name: test_name.into(), video_file: video_path.file_name().unwrap().to_string_lossy().into(), total_time_ms: total_time, frame_extraction_time_ms: extraction_time, keyframe_analysis_time_ms: analysis_time, total_frames: frames.len(), keyframes_extracted: keyframes.len(), keyframe_ratio: keyframes.len() as f64 / frames.len() as f64 * 100.0, processing_fps: frames.len() as f64 / (total_time/1000.0), max_keyframes_requested: max_keyframes, optimization_type, simd_enabled: use_simd, threads_used: rayon::current_num_threads(), timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string() };
+    let result = PerformanceResult { test_name: test_name.into(), video_file: input.display_name(), total_time_ms: total_time, frame_extraction_time_ms: extraction_time, keyframe_analysis_time_ms: analysis_time, total_frames: frames.len(), keyframes_extracted: keyframes.len(), keyframe_ratio: keyframes.len() as f64 / frames.len() as f64 * 100.0, processing_fps: frames.len() as f64 / (total_time/1000.0), max_keyframes_requested: max_keyframes, optimization_type, simd_enabled: use_simd, threads_used: pool.current_num_threads(), timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string() };
     vprintln!(verbose, "Result: frames={} keyframes={} requested={} time_ms={:.2} fps={:.1}", result.total_frames, result.keyframes_extracted, max_keyframes, result.total_time_ms, result.processing_fps);
     Ok(result)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::store::FrameData;
+
+    fn frames(n: usize) -> Vec<PyVideoFrame> { (0..n).map(|i| PyVideoFrame::from_data(i, 1, 1, FrameData::Owned(vec![0]), i as f64)).collect() }
+
+    #[test]
+    fn uniform_spreads_picks_across_video() {
+        let frames = frames(100);
+        let diffs: Vec<(usize, f64)> = (1..100).map(|i| (i, 1.0)).collect();
+        assert_eq!(select_by_strategy(&frames, diffs, 4, &SelectionStrategy::Uniform, None).unwrap(), vec![12, 37, 62, 87]);
+    }
+
+    #[test]
+    fn uniform_with_more_slots_than_candidates_keeps_all_candidates() {
+        let frames = frames(100);
+        let diffs = vec![(10, 1.0), (50, 1.0), (90, 1.0)];
+        assert_eq!(select_by_strategy(&frames, diffs, 10, &SelectionStrategy::Uniform, None).unwrap(), vec![10, 50, 90]);
+    }
+}
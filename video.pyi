@@ -1,15 +1,136 @@
 """inkfox.video submodule type stubs"""
 from __future__ import annotations
-from typing import Any, Dict, List, Sequence, Tuple
+import os
+from typing import Any, BinaryIO, Dict, Iterable, List, Sequence, Tuple, Union
 
 class PyVideoFrame:
     frame_number: int
     width: int
     height: int
-    def __init__(self, frame_number: int, width: int, height: int, data: bytes | bytearray | memoryview | list[int]): ...
+    timestamp: float
+    # data must hold at least width*height bytes (ValueError otherwise); extra bytes are ignored
+    def __init__(self, frame_number: int, width: int, height: int, data: bytes | bytearray | memoryview | list[int], timestamp: float | None = None): ...
     def get_data(self) -> bytes: ...
-    def calculate_difference(self, other: "PyVideoFrame") -> float: ...
-    def calculate_difference_simd(self, other: "PyVideoFrame", block_size: int | None = None) -> float: ...
+    @property
+    def spilled(self) -> bool: ...  # pixels live in the memory-mapped spill file (see ram_budget_mb)
+    def calculate_difference(self, other: "PyVideoFrame", mask: PyFrameMask | None = None) -> float: ...
+    def calculate_difference_simd(self, other: "PyVideoFrame", block_size: int | None = None, mask: PyFrameMask | None = None) -> float: ...
+    def crop(self, rect: PyCropRect) -> PyVideoFrame: ...
+    def sharpness(self) -> float: ...
+    def exposure(self) -> float: ...
+    def contrast(self) -> float: ...
+    def entropy(self) -> float: ...
+    def quality_score(self) -> float: ...
+    def quality_metrics(self) -> dict[str, float]: ...
+
+Rect = Tuple[int, int, int, int]  # (x, y, w, h)
+# Paths may contain non-UTF-8 bytes; in-memory data is piped to ffmpeg's stdin (or decoded natively);
+# file objects are read fully
+VideoInput = Union[str, os.PathLike[str], bytes, bytearray, memoryview, BinaryIO, "PyFrameSource"]
+PathArg = Union[str, os.PathLike[str]]
+
+class PyFrameMask:
+    width: int
+    height: int
+    def __init__(self, width: int, height: int, include: Sequence[Rect] | None = None, exclude: Sequence[Rect] | None = None) -> None: ...
+    @staticmethod
+    def from_frame(frame: PyVideoFrame, threshold: int = 128) -> PyFrameMask: ...
+    @property
+    def active_pixels(self) -> int: ...
+    @property
+    def coverage(self) -> float: ...
+
+class PyCropRect:
+    x: int
+    y: int
+    width: int
+    height: int
+    def __init__(self, x: int, y: int, width: int, height: int) -> None: ...
+    def to_ffmpeg_filter(self) -> str: ...
+    def to_tuple(self) -> tuple[int, int, int, int]: ...
+
+class PyFrameStats:
+    frame_indices: list[int]
+    timestamps: list[float]
+    mean: list[float]
+    variance: list[float]
+    min: list[int]
+    max: list[int]
+    entropy: list[float]
+    edge_density: list[float]
+    def __len__(self) -> int: ...
+    def to_dict(self) -> dict[str, list[Any]]: ...
+
+class PyFrozenSegment:
+    start_frame: int
+    end_frame: int
+    start_time: float
+    end_time: float
+    duration: float
+
+class PyFrameSource:
+    """Lazily opened frame source; pass to extract_frames / extract_frame_stats"""
+    @staticmethod
+    def from_path(path: str | os.PathLike[str]) -> PyFrameSource: ...  # directory -> images, Y4M / GIF / APNG / WebP by header, else ffmpeg
+    @staticmethod
+    def ffmpeg(path: str | os.PathLike[str], workers: int | None = None) -> PyFrameSource: ...  # workers defaults to the extractor's decode_workers
+    @staticmethod
+    def y4m(path: str | os.PathLike[str]) -> PyFrameSource: ...
+    @staticmethod
+    def image_dir(path: str | os.PathLike[str], fps: float | None = None) -> PyFrameSource: ...
+    @staticmethod
+    def raw_gray(path: str | os.PathLike[str], width: int, height: int, fps: float | None = None) -> PyFrameSource: ...
+    @staticmethod
+    def from_frames(frames: Iterable[PyVideoFrame]) -> PyFrameSource: ...  # consumed once
+    @property
+    def kind(self) -> str: ...
+
+class PyLoopInfo:
+    start_frame: int
+    period: int
+    cycles: float
+    mean_diff: float
+    @property
+    def end_frame(self) -> int: ...
+
+class PyEncodedKeyframe:
+    frame_index: int
+    timestamp: float
+    score: float | None
+    format: str  # "jpeg" / "png" / "webp"
+    width: int
+    height: int
+    base64: str | None  # only when base64=True was requested
+    @property
+    def data(self) -> bytes: ...
+    @property
+    def mime_type(self) -> str: ...
+    def data_uri(self) -> str: ...
+    def __len__(self) -> int: ...
+
+class PyStoryboard:
+    width: int
+    height: int
+    columns: int
+    rows: int
+    format: str
+    frame_indices: list[int]  # row-major tile order
+    timestamps: list[float]
+    tiles: list[Tuple[int, int, int, int]]  # (x, y, width, height) per tile
+    @property
+    def data(self) -> bytes: ...
+    def data_uri(self) -> str: ...
+    def save(self, path: PathArg) -> None: ...
+
+class PySpriteSheet:
+    vtt_path: str
+    sprite_paths: list[str]
+    frame_indices: list[int]
+    timestamps: list[float]
+    tile_width: int
+    tile_height: int
+    columns: int
+    def __len__(self) -> int: ...
 
 class PyPerformanceResult:
     test_name: str
@@ -28,13 +149,292 @@ class PyPerformanceResult:
     timestamp: str
     def to_dict(self) -> dict[str, Any]: ...
 
+class PyBatchItem:
+    video_file: str
+    output_dir: str  # output_dir/<video stem>, suffixed _1, _2 ... on duplicate stems
+    result: PyPerformanceResult | None
+    error: str | None
+    @property
+    def ok(self) -> bool: ...
+
+class PyBatchResult:
+    items: list[PyBatchItem]  # in input order
+    summary: PyPerformanceResult  # wall-clock total_time_ms; frames / keyframes / stage times summed over successes
+    succeeded: int
+    failed: int
+    def errors(self) -> list[tuple[str, str]]: ...  # (video_file, error) in input order
+    def __len__(self) -> int: ...
+
+class PyTransitionSegment:
+    kind: str  # "fade_in" | "fade_out" | "dissolve" | "black" | "white" | "blank"
+    start_frame: int
+    end_frame: int
+    @property
+    def length(self) -> int: ...
+
+class PyDifferenceCurve:
+    frame_indices: list[int]
+    timestamps: list[float]
+    scores: list[float]
+    def __len__(self) -> int: ...
+    def to_list(self) -> list[tuple[int, float, float]]: ...
+    def to_bytes(self) -> bytes:
+        """Row-major native-endian float64 (n, 3): np.frombuffer(buf).reshape(-1, 3)"""
+        ...
+
 class VideoKeyframeExtractor:
-    def __init__(self, ffmpeg_path: str = ..., threads: int = 0, verbose: bool = False) -> None: ...
-    def extract_frames(self, video_path: str, max_frames: int | None = None) -> tuple[list[PyVideoFrame], int, int]: ...
-    def extract_keyframes(self, frames: Sequence[PyVideoFrame], max_keyframes: int, use_simd: bool | None = None, block_size: int | None = None) -> list[int]: ...
-    def save_keyframes(self, video_path: str, keyframe_indices: Sequence[int], output_dir: str, max_save: int | None = None) -> int: ...
-    def benchmark(self, video_path: str, max_keyframes: int, test_name: str, use_simd: bool | None = None, block_size: int | None = None) -> PyPerformanceResult: ...
-    def process_video(self, video_path: str, output_dir: str, max_keyframes: int, max_save: int | None = None, use_simd: bool | None = None, block_size: int | None = None) -> PyPerformanceResult: ...
+    # threads sizes this extractor's own thread pool (0 = rayon default); all analysis runs in it and the
+    # global pool is never modified, so extractors with different thread counts can coexist.
+    # Frames decoded by ffmpeg carry their presentation timestamps (variable-frame-rate input included).
+    # decode_workers > 1 splits ffmpeg input into time ranges decoded concurrently by parallel ffmpeg
+    # processes (each buffering its range under ram_budget_mb / workers) and stitched in order; frames and
+    # timestamps match serial decoding. In-memory input is spilled to a temp file first; short videos
+    # (< 2 s per worker) fall back to a single process.
+    # ram_budget_mb caps resident pixel memory of collected frame lists; frames beyond it are written to an
+    # anonymous temp file (in TMPDIR) and returned as lazy memory-mapped views. None keeps everything in RAM.
+    def __init__(self, ffmpeg_path: str = ..., threads: int = 0, verbose: bool = False, decode_workers: int = 1, ram_budget_mb: int | None = None) -> None: ...
+    # GIF / APNG / WebP (animated or still) are decoded natively; timestamps follow per-frame delays
+    def extract_frames(self, video_path: VideoInput, max_frames: int | None = None, ram_budget_mb: int | None = None) -> tuple[list[PyVideoFrame], int, int]: ...
+    def frame_stats(self, frames: Sequence[PyVideoFrame], edge_threshold: int | None = None) -> PyFrameStats: ...
+    def extract_frame_stats(self, video_path: VideoInput, edge_threshold: int | None = None) -> PyFrameStats: ...
+    def extract_keyframes(
+        self,
+        frames: Sequence[PyVideoFrame],
+        max_keyframes: int,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        exclude_blank: bool | None = None,
+        snap_transitions: bool | None = None,
+        refine_window: int | None = None,
+        strategy: str | None = None,  # "top_k" | "threshold" | "uniform" | "segment_quota" | "diverse" | "slides"
+        threshold: float | None = None,
+        mask: PyFrameMask | None = None,
+        crop: PyCropRect | None = None,
+        collapse_frozen: bool | None = None,
+        single_cycle: bool | None = None,
+    ) -> list[int]: ...
+    def detect_crop(
+        self,
+        frames: Sequence[PyVideoFrame],
+        limit: float | None = None,
+        round: int | None = None,
+        sample_step: int | None = None,
+    ) -> PyCropRect | None: ...
+    def extract_keyframes_diverse(
+        self,
+        frames: Sequence[PyVideoFrame],
+        max_keyframes: int,
+        feature: str | None = None,  # "thumbnail" | "histogram" | "phash"
+        method: str | None = None,  # "farthest" | "kmedoids"
+        candidates: int | None = None,
+        min_distance: float | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+    ) -> list[int]: ...
+    def difference_curve(
+        self,
+        frames: Sequence[PyVideoFrame],
+        smoothing: int | None = None,
+        normalize: bool | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        mask: PyFrameMask | None = None,
+    ) -> PyDifferenceCurve: ...
+    def extract_slide_keyframes(
+        self,
+        frames: Sequence[PyVideoFrame],
+        max_keyframes: int | None = None,
+        grid: int | None = None,
+        change_threshold: float | None = None,
+        cursor_size: int | None = None,
+        new_slide_ratio: float | None = None,
+        min_stable_frames: int | None = None,
+        mask: PyFrameMask | None = None,
+    ) -> list[int]: ...
+    def detect_loop(
+        self,
+        frames: Sequence[PyVideoFrame],
+        max_diff: float | None = None,
+        min_period: int | None = None,
+        max_period: int | None = None,
+        min_cycles: float | None = None,
+    ) -> PyLoopInfo | None: ...
+    def detect_frozen_segments(
+        self,
+        frames: Sequence[PyVideoFrame],
+        noise_tolerance: float | None = None,
+        min_duration: float | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        mask: PyFrameMask | None = None,
+    ) -> list[PyFrozenSegment]: ...
+    def detect_transitions(
+        self,
+        frames: Sequence[PyVideoFrame],
+        black_threshold: float | None = None,
+        white_threshold: float | None = None,
+        blank_variance: float | None = None,
+        min_fade_frames: int | None = None,
+        min_dissolve_frames: int | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+    ) -> list[PyTransitionSegment]: ...
+    # template placeholders: {video_stem} {index} {frame} {pts_ms} {score}; "{frame:06}" sets zero padding,
+    # "{score:.3}" sets decimals. Default "keyframe_{index}" (index padded to at least 3 digits); the
+    # extension follows `format`. on_conflict: overwrite (default) / skip / unique. Returns files written.
+    # Unknown placeholders or unbalanced braces raise ValueError.
+    def save_keyframes(
+        self,
+        video_path: VideoInput,
+        keyframe_indices: Sequence[int],
+        output_dir: PathArg,
+        max_save: int | None = None,
+        crop: PyCropRect | None = None,
+        template: str | None = None,
+        format: str | None = None,
+        quality: int | None = None,
+        max_dimension: int | None = None,
+        on_conflict: str | None = None,
+        scores: Sequence[float] | None = None,
+    ) -> int: ...
+    # In-memory encoding, no output files written. format: jpeg/png/webp (png/webp lossless, quality ignored).
+    # crop and max_dimension are applied by ffmpeg while decoding, so full-resolution frames are never held.
+    # max_bytes: per-image budget; JPEG quality is lowered first, then the image is downscaled.
+    # In-memory input that ffmpeg cannot probe from a pipe (e.g. MP4 with moov at the end) is written to a
+    # temp file in TMPDIR for the duration of the call.
+    def encode_keyframes(
+        self,
+        video_path: VideoInput,
+        keyframe_indices: Sequence[int],
+        format: str | None = None,
+        max_dimension: int | None = None,
+        quality: int | None = None,
+        max_bytes: int | None = None,
+        base64: bool | None = None,
+        crop: PyCropRect | None = None,
+        scores: Sequence[float] | None = None,
+    ) -> list[PyEncodedKeyframe]: ...
+    def process_video_encoded(
+        self,
+        video_path: VideoInput,
+        max_keyframes: int,
+        format: str | None = None,
+        max_dimension: int | None = None,
+        quality: int | None = None,
+        max_bytes: int | None = None,
+        base64: bool | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        exclude_blank: bool | None = None,
+        snap_transitions: bool | None = None,
+        refine_window: int | None = None,
+        strategy: str | None = None,
+        threshold: float | None = None,
+        mask: PyFrameMask | None = None,
+        auto_crop: bool | None = None,
+        collapse_frozen: bool | None = None,
+        single_cycle: bool | None = None,
+    ) -> list[PyEncodedKeyframe]: ...
+    # columns=None picks a near-square grid; tile_height=None follows the first frame's aspect ratio
+    def build_storyboard(
+        self,
+        video_path: VideoInput,
+        keyframe_indices: Sequence[int],
+        columns: int | None = None,
+        tile_width: int | None = None,
+        tile_height: int | None = None,
+        padding: int | None = None,
+        timecodes: bool | None = None,
+        format: str | None = None,
+        quality: int | None = None,
+        crop: PyCropRect | None = None,
+    ) -> PyStoryboard: ...
+    # Writes {prefix}_000.jpg ... and {prefix}.vtt into output_dir. Sample every `interval` seconds
+    # (default 10) or at `keyframe_indices`, not both. Cues point at "{url_prefix}{sprite}#xywh=x,y,w,h".
+    def generate_sprites(
+        self,
+        video_path: VideoInput,
+        output_dir: PathArg,
+        interval: float | None = None,
+        keyframe_indices: Sequence[int] | None = None,
+        tile_width: int | None = None,
+        tile_height: int | None = None,
+        columns: int | None = None,
+        rows: int | None = None,
+        prefix: str | None = None,
+        url_prefix: str | None = None,
+        format: str | None = None,
+        quality: int | None = None,
+    ) -> PySpriteSheet: ...
+    # One GIF/WebP/MP4 highlight file. window=0 shows each keyframe for `hold` seconds; window>0 cuts
+    # that many seconds around each keyframe (overlaps merged). loop_count: 0 infinite, -1 play once.
+    # Returns the preview duration in seconds; format defaults to the output extension.
+    def export_preview(
+        self,
+        video_path: VideoInput,
+        keyframe_indices: Sequence[int],
+        output_path: PathArg,
+        format: str | None = None,
+        window: float | None = None,
+        hold: float | None = None,
+        fps: float | None = None,
+        width: int | None = None,
+        loop_count: int | None = None,
+        quality: int | None = None,
+        crop: PyCropRect | None = None,
+    ) -> float: ...
+    def benchmark(self, video_path: VideoInput, max_keyframes: int, test_name: str, use_simd: bool | None = None, block_size: int | None = None) -> PyPerformanceResult: ...
+    # Decodes and selects once; the result reports the keyframes actually selected and the decode/analysis
+    # times (total includes saving).
+    def process_video(
+        self,
+        video_path: VideoInput,
+        output_dir: PathArg,
+        max_keyframes: int,
+        max_save: int | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        exclude_blank: bool | None = None,
+        snap_transitions: bool | None = None,
+        refine_window: int | None = None,
+        strategy: str | None = None,
+        threshold: float | None = None,
+        mask: PyFrameMask | None = None,
+        auto_crop: bool | None = None,
+        collapse_frozen: bool | None = None,
+        single_cycle: bool | None = None,
+        template: str | None = None,
+        format: str | None = None,
+        quality: int | None = None,
+        max_dimension: int | None = None,
+        on_conflict: str | None = None,
+    ) -> PyPerformanceResult: ...
+    # Runs process_video for each input with at most max_concurrent (default 2) decoding at once; analysis
+    # shares the thread pool. A failing video is reported in its PyBatchItem and does not abort the batch.
+    def process_videos(
+        self,
+        paths: Sequence[VideoInput],
+        output_dir: PathArg,
+        max_keyframes: int,
+        max_concurrent: int | None = None,
+        max_save: int | None = None,
+        use_simd: bool | None = None,
+        block_size: int | None = None,
+        exclude_blank: bool | None = None,
+        snap_transitions: bool | None = None,
+        refine_window: int | None = None,
+        strategy: str | None = None,
+        threshold: float | None = None,
+        mask: PyFrameMask | None = None,
+        auto_crop: bool | None = None,
+        collapse_frozen: bool | None = None,
+        single_cycle: bool | None = None,
+        template: str | None = None,
+        format: str | None = None,
+        quality: int | None = None,
+        max_dimension: int | None = None,
+        on_conflict: str | None = None,
+    ) -> PyBatchResult: ...
     def get_cpu_features(self) -> dict[str, bool]: ...
     def get_thread_count(self) -> int: ...
     def get_configured_threads(self) -> int: ...
@@ -43,8 +443,8 @@ class VideoKeyframeExtractor:
 # Re-export convenience functions (top-level also provides these)
 
 def extract_keyframes_from_video(
-    video_path: str,
-    output_dir: str,
+    video_path: VideoInput,
+    output_dir: PathArg,
     max_keyframes: int,
     max_save: int | None = None,
     ffmpeg_path: str | None = None,
@@ -54,12 +454,28 @@ def extract_keyframes_from_video(
     block_size: int | None = None,
 ): ...
 
+# "threads" and "global_threads" both report the global rayon pool size ("threads" is kept for existing
+# callers); extractors and indexes use their own pools
+# (see VideoKeyframeExtractor.get_thread_count / PyMetadataIndex.get_thread_count).
 def get_system_info() -> dict[str, Any]: ...
 
 __all__ = [
     "PyVideoFrame",
     "PyPerformanceResult",
     "VideoKeyframeExtractor",
+    "PyTransitionSegment",
+    "PyDifferenceCurve",
+    "PyFrameMask",
+    "PyCropRect",
+    "PyFrameStats",
+    "PyFrozenSegment",
+    "PyLoopInfo",
+    "PyFrameSource",
+    "PyEncodedKeyframe",
+    "PyStoryboard",
+    "PySpriteSheet",
+    "PyBatchItem",
+    "PyBatchResult",
     "extract_keyframes_from_video",
     "get_system_info",
 ]